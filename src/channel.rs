@@ -0,0 +1,372 @@
+use crate::click_ids;
+use crate::exports::edgee::components::data_collection::PageData;
+use crate::helpers::url_host;
+use crate::referrer::{host_matches, is_search_source, is_social_source, Referrer};
+use crate::utm::UtmParams;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// One rule of the channel grouping. Every non-empty condition must match;
+/// values inside a condition are alternatives. `medium` and `source` accept
+/// `*` wildcards and are compared case-insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelRule {
+    pub channel: String,
+    #[serde(default)]
+    pub medium: Vec<String>,
+    #[serde(default)]
+    pub source: Vec<String>,
+    /// `search` or `social`, derived from the campaign source or the referrer.
+    #[serde(default)]
+    pub source_category: Vec<String>,
+    #[serde(default)]
+    pub click_id: Vec<String>,
+    #[serde(default)]
+    pub has_referrer: Option<bool>,
+}
+
+/// The campaign signals a visit is classified from.
+#[derive(Debug, Clone, Default)]
+pub struct Touchpoint {
+    pub medium: String,
+    pub source: String,
+    pub source_categories: Vec<&'static str>,
+    pub click_ids: Vec<String>,
+    pub has_referrer: bool,
+}
+
+impl Touchpoint {
+    /// A referrer on the page's own host or on one of `internal_hostnames` is a
+    /// self-referral, such as internal navigation, and does not count as a referrer.
+    pub fn new(
+        utm: &UtmParams,
+        page: &PageData,
+        referrer: &Referrer,
+        internal_hostnames: &[String],
+    ) -> Self {
+        let medium = utm.medium.trim().to_lowercase();
+        let source = utm.source.trim().to_lowercase();
+
+        let mut source_categories = Vec::new();
+        if referrer.search_engine.is_some() || is_search_source(&source) {
+            source_categories.push("search");
+        }
+        if referrer.social_network.is_some() || is_social_source(&source) {
+            source_categories.push("social");
+        }
+
+        let click_ids = click_ids::extract(page)
+            .into_iter()
+            .map(|(param, _)| param.to_string())
+            .collect();
+
+        let self_referral = referrer.domain.as_deref().is_some_and(|domain| {
+            url_host(&page.url).as_deref() == Some(domain)
+                || internal_hostnames.iter().any(|h| host_matches(domain, h))
+        });
+
+        Self {
+            medium,
+            source,
+            source_categories,
+            click_ids,
+            has_referrer: referrer.domain.is_some() && !self_referral,
+        }
+    }
+
+    fn is_direct(&self) -> bool {
+        let no_campaign = (self.source.is_empty() || self.source == "(direct)")
+            && matches!(self.medium.as_str(), "" | "(none)" | "(not set)");
+        no_campaign && self.click_ids.is_empty() && !self.has_referrer
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelRules {
+    pub rules: Vec<ChannelRule>,
+}
+
+impl ChannelRules {
+    /// Reads the `channel_rules` setting, a JSON array of [`ChannelRule`],
+    /// falling back to a GA4-style default channel grouping.
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        match settings_map
+            .get("channel_rules")
+            .filter(|v| !v.trim().is_empty())
+        {
+            Some(json) => {
+                let rules = serde_json::from_str(json)
+                    .map_err(|e| anyhow::anyhow!("Invalid 'channel_rules' setting: {e}"))?;
+                Ok(Self { rules })
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn classify(&self, touchpoint: &Touchpoint) -> String {
+        if touchpoint.is_direct() {
+            return "Direct".into();
+        }
+
+        self.rules
+            .iter()
+            .find(|rule| rule_matches(rule, touchpoint))
+            .map(|rule| rule.channel.clone())
+            .unwrap_or_else(|| "Unassigned".into())
+    }
+}
+
+impl Default for ChannelRules {
+    fn default() -> Self {
+        const PAID_MEDIUMS: &[&str] = &["*cp*", "ppc", "retargeting", "paid*"];
+
+        let rule = |channel: &str| ChannelRule {
+            channel: channel.into(),
+            ..Default::default()
+        };
+        let list = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        Self {
+            rules: vec![
+                ChannelRule {
+                    click_id: list(&["gclid", "gbraid", "wbraid", "msclkid"]),
+                    ..rule("Paid Search")
+                },
+                ChannelRule {
+                    source_category: list(&["search"]),
+                    medium: list(PAID_MEDIUMS),
+                    ..rule("Paid Search")
+                },
+                ChannelRule {
+                    click_id: list(&["ttclid", "twclid", "li_fat_id"]),
+                    ..rule("Paid Social")
+                },
+                ChannelRule {
+                    source_category: list(&["social"]),
+                    medium: list(PAID_MEDIUMS),
+                    ..rule("Paid Social")
+                },
+                ChannelRule {
+                    click_id: list(&["dclid"]),
+                    ..rule("Display")
+                },
+                ChannelRule {
+                    medium: list(&["display", "banner", "expandable", "interstitial", "cpm"]),
+                    ..rule("Display")
+                },
+                ChannelRule {
+                    medium: list(&["email", "e-mail", "e_mail", "e mail"]),
+                    ..rule("Email")
+                },
+                ChannelRule {
+                    source: list(&["email", "e-mail", "e_mail", "e mail", "newsletter"]),
+                    ..rule("Email")
+                },
+                ChannelRule {
+                    medium: list(&["affiliate*"]),
+                    ..rule("Affiliate")
+                },
+                ChannelRule {
+                    source_category: list(&["social"]),
+                    ..rule("Organic Social")
+                },
+                ChannelRule {
+                    medium: list(&[
+                        "social",
+                        "social-network",
+                        "social-media",
+                        "sm",
+                        "social network",
+                        "social media",
+                    ]),
+                    ..rule("Organic Social")
+                },
+                ChannelRule {
+                    click_id: list(&["fbclid"]),
+                    ..rule("Organic Social")
+                },
+                ChannelRule {
+                    source_category: list(&["search"]),
+                    ..rule("Organic Search")
+                },
+                ChannelRule {
+                    medium: list(&["organic"]),
+                    ..rule("Organic Search")
+                },
+                ChannelRule {
+                    medium: list(&["referral", "app", "link"]),
+                    ..rule("Referral")
+                },
+                ChannelRule {
+                    has_referrer: Some(true),
+                    ..rule("Referral")
+                },
+            ],
+        }
+    }
+}
+
+fn rule_matches(rule: &ChannelRule, touchpoint: &Touchpoint) -> bool {
+    let any_glob = |patterns: &[String], value: &str| {
+        patterns.is_empty()
+            || patterns
+                .iter()
+                .any(|p| glob_matches(&p.to_lowercase(), value))
+    };
+
+    any_glob(&rule.medium, &touchpoint.medium)
+        && any_glob(&rule.source, &touchpoint.source)
+        && (rule.source_category.is_empty()
+            || rule.source_category.iter().any(|c| {
+                touchpoint
+                    .source_categories
+                    .contains(&c.to_lowercase().as_str())
+            }))
+        && (rule.click_id.is_empty()
+            || rule
+                .click_id
+                .iter()
+                .any(|id| touchpoint.click_ids.contains(id)))
+        && rule
+            .has_referrer
+            .is_none_or(|expected| expected == touchpoint.has_referrer)
+}
+
+/// Matches `value` against `pattern`, where `*` stands for any sequence of characters.
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touchpoint(medium: &str, source: &str, url: &str, referrer: &str) -> Touchpoint {
+        let utm = UtmParams {
+            source: source.into(),
+            medium: medium.into(),
+            ..Default::default()
+        };
+        let page = PageData {
+            name: String::new(),
+            category: String::new(),
+            keywords: vec![],
+            title: String::new(),
+            url: url.into(),
+            path: String::new(),
+            search: String::new(),
+            referrer: referrer.into(),
+            properties: vec![],
+        };
+        Touchpoint::new(
+            &utm,
+            &page,
+            &Referrer::parse(referrer),
+            &["example.net".to_string()],
+        )
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_matches("*cp*", "cpc"));
+        assert!(glob_matches("*cp*", "display-cpm"));
+        assert!(glob_matches("paid*", "paid_social"));
+        assert!(glob_matches("email", "email"));
+        assert!(!glob_matches("email", "emails"));
+        assert!(!glob_matches("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn default_grouping() {
+        let rules = ChannelRules::default();
+        let classify = |medium, source, url, referrer| {
+            rules.classify(&touchpoint(medium, source, url, referrer))
+        };
+
+        assert_eq!(classify("", "", "https://shop.example.com/", ""), "Direct");
+        assert_eq!(
+            classify("", "", "https://shop.example.com/?gclid=abc", ""),
+            "Paid Search"
+        );
+        assert_eq!(classify("cpc", "google", "", ""), "Paid Search");
+        assert_eq!(classify("paid", "facebook", "", ""), "Paid Social");
+        assert_eq!(
+            classify("", "", "", "https://l.facebook.com/"),
+            "Organic Social"
+        );
+        assert_eq!(
+            classify("", "", "", "https://www.google.fr/"),
+            "Organic Search"
+        );
+        assert_eq!(classify("email", "newsletter", "", ""), "Email");
+        assert_eq!(classify("affiliate", "partner", "", ""), "Affiliate");
+        assert_eq!(classify("banner", "network", "", ""), "Display");
+        assert_eq!(
+            classify("", "", "", "https://blog.example.org/"),
+            "Referral"
+        );
+        assert_eq!(classify("print", "flyer", "", ""), "Unassigned");
+    }
+
+    #[test]
+    fn self_referrals_are_not_referrals() {
+        let rules = ChannelRules::default();
+        let classify = |url, referrer| rules.classify(&touchpoint("", "", url, referrer));
+
+        assert_eq!(
+            classify("https://shop.example.com/cart", "https://shop.example.com/"),
+            "Direct"
+        );
+        assert_eq!(
+            classify(
+                "https://shop.example.com/",
+                "https://checkout.example.net/done"
+            ),
+            "Direct"
+        );
+        assert_eq!(
+            classify("https://shop.example.com/", "https://blog.example.com/"),
+            "Referral"
+        );
+    }
+
+    #[test]
+    fn custom_rules_replace_defaults() {
+        let settings = HashMap::from([(
+            "channel_rules".to_string(),
+            r#"[{"channel": "Partners", "source": ["partner-*"]}]"#.to_string(),
+        )]);
+        let rules = ChannelRules::from_settings(&settings).unwrap();
+
+        assert_eq!(
+            rules.classify(&touchpoint("cpc", "partner-acme", "", "")),
+            "Partners"
+        );
+        assert_eq!(
+            rules.classify(&touchpoint("cpc", "google", "", "")),
+            "Unassigned"
+        );
+
+        let invalid = HashMap::from([("channel_rules".to_string(), "{".to_string())]);
+        assert!(ChannelRules::from_settings(&invalid).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::exports::edgee::components::data_collection::PageData;
+use crate::helpers::parse_query;
+use std::collections::HashMap;
+
+/// Query parameters ad platforms append to identify a click.
+pub const CLICK_ID_PARAMS: &[&str] = &[
+    "gclid",
+    "gbraid",
+    "wbraid",
+    "dclid",
+    "msclkid",
+    "fbclid",
+    "ttclid",
+    "twclid",
+    "li_fat_id",
+];
+
+/// Returns the click IDs found in `page.url` and `page.search`, in
+/// [`CLICK_ID_PARAMS`] order. The first non-empty value of each parameter wins.
+pub fn extract(page: &PageData) -> Vec<(&'static str, String)> {
+    let query: Vec<(String, String)> = parse_query(&page.url)
+        .into_iter()
+        .chain(parse_query(&page.search))
+        .collect();
+
+    CLICK_ID_PARAMS
+        .iter()
+        .filter_map(|param| {
+            query
+                .iter()
+                .find(|(key, value)| key == param && !value.trim().is_empty())
+                .map(|(_, value)| (*param, value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Adds each click ID as an event property named after its query parameter.
+pub fn enrich(props: &mut HashMap<String, String>, page: &PageData) {
+    for (param, value) in extract(page) {
+        props.insert(param.into(), value);
+    }
+}
+
+/// First-touch profile properties (`initial_gclid`, ...), meant for `$set_once`.
+pub fn first_touch_properties(page: &PageData) -> HashMap<String, String> {
+    extract(page)
+        .into_iter()
+        .map(|(param, value)| (format!("initial_{param}"), value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, search: &str) -> PageData {
+        PageData {
+            name: String::new(),
+            category: String::new(),
+            keywords: vec![],
+            title: String::new(),
+            url: url.into(),
+            path: String::new(),
+            search: search.into(),
+            referrer: String::new(),
+            properties: vec![],
+        }
+    }
+
+    #[test]
+    fn extracts_click_ids_from_url_and_search() {
+        let page = page(
+            "https://shop.example.com/?gclid=abc&utm_source=google&fbclid=",
+            "?fbclid=IwAR0&msclkid=ms1&gclid=ignored",
+        );
+        assert_eq!(
+            extract(&page),
+            vec![
+                ("gclid", "abc".to_string()),
+                ("msclkid", "ms1".to_string()),
+                ("fbclid", "IwAR0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_touch_properties_are_prefixed() {
+        let props = first_touch_properties(&page("https://example.com/?ttclid=tt1", ""));
+        assert_eq!(props.get("initial_ttclid"), Some(&"tt1".to_string()));
+        assert_eq!(props.len(), 1);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::helpers::parse_list;
+use std::collections::HashMap;
+
+/// The context groups enriching events and profiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextGroups {
+    pub page: bool,
+    pub campaign: bool,
+    pub session: bool,
+    pub client: bool,
+}
+
+impl ContextGroups {
+    pub const ALL: Self = Self {
+        page: true,
+        campaign: true,
+        session: true,
+        client: true,
+    };
+
+    /// Reads a comma-separated list of groups (`page`, `campaign`, `session`,
+    /// `client`). All groups are enabled when the setting is absent or empty,
+    /// none when it is `none`.
+    fn from_setting(settings_map: &HashMap<String, String>, key: &str) -> anyhow::Result<Self> {
+        let Some(value) = settings_map.get(key).filter(|v| !v.trim().is_empty()) else {
+            return Ok(Self::ALL);
+        };
+
+        let mut groups = Self {
+            page: false,
+            campaign: false,
+            session: false,
+            client: false,
+        };
+        for group in parse_list(Some(value)) {
+            match group.to_lowercase().as_str() {
+                "page" => groups.page = true,
+                "campaign" => groups.campaign = true,
+                "session" => groups.session = true,
+                "client" => groups.client = true,
+                "all" => groups = Self::ALL,
+                "none" => {}
+                other => {
+                    return Err(anyhow::anyhow!(
+                        "Invalid '{key}' setting: '{other}' (expected page, campaign, session or client)"
+                    ))
+                }
+            }
+        }
+
+        Ok(groups)
+    }
+}
+
+/// Context groups enabled for each event type and for profile updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnrichmentToggles {
+    pub page: ContextGroups,
+    pub track: ContextGroups,
+    pub profile: ContextGroups,
+}
+
+impl EnrichmentToggles {
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        Ok(Self {
+            page: ContextGroups::from_setting(settings_map, "page_enrichment")?,
+            track: ContextGroups::from_setting(settings_map, "track_enrichment")?,
+            profile: ContextGroups::from_setting(settings_map, "profile_enrichment")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_group_lists() {
+        let settings = HashMap::from([
+            ("track_enrichment".to_string(), "page, Campaign".to_string()),
+            ("profile_enrichment".to_string(), "none".to_string()),
+        ]);
+        let toggles = EnrichmentToggles::from_settings(&settings).unwrap();
+
+        assert_eq!(toggles.page, ContextGroups::ALL);
+        assert_eq!(
+            toggles.track,
+            ContextGroups {
+                page: true,
+                campaign: true,
+                session: false,
+                client: false,
+            }
+        );
+        assert!(!toggles.profile.client && !toggles.profile.page);
+
+        let empty = HashMap::from([("page_enrichment".to_string(), " ".to_string())]);
+        assert_eq!(
+            EnrichmentToggles::from_settings(&empty).unwrap().page,
+            ContextGroups::ALL
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_groups() {
+        let settings = HashMap::from([("track_enrichment".to_string(), "geo".to_string())]);
+        assert!(EnrichmentToggles::from_settings(&settings).is_err());
+    }
+}
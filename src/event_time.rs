@@ -0,0 +1,137 @@
+use crate::exports::edgee::components::data_collection::Event;
+use std::collections::HashMap;
+
+/// How far ahead of the component's clock an event may be before its
+/// timestamp is considered bogus.
+const MAX_CLOCK_SKEW_MILLIS: i64 = 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimePrecision {
+    Milliseconds,
+    Seconds,
+}
+
+impl TimePrecision {
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        match settings_map
+            .get("time_precision")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("milliseconds") | Some("ms") => Ok(TimePrecision::Milliseconds),
+            Some("seconds") | Some("s") => Ok(TimePrecision::Seconds),
+            Some(other) => Err(anyhow::anyhow!(
+                "Invalid 'time_precision' setting: '{other}' (expected 'milliseconds' or 'seconds')"
+            )),
+        }
+    }
+}
+
+/// The `time` sent to Mixpanel, in milliseconds since the epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventTime {
+    pub millis: i64,
+    /// Set when `timestamp_millis` was zero or in the future and another
+    /// source was used instead.
+    pub corrected: bool,
+}
+
+impl EventTime {
+    /// Picks the first plausible timestamp among `timestamp_millis`,
+    /// `timestamp_micros` and `timestamp`, falling back to `now_millis`.
+    pub fn resolve(event: &Event, now_millis: i64) -> Self {
+        Self::from_candidates(
+            [
+                event.timestamp_millis,
+                event.timestamp_micros / 1_000,
+                event.timestamp.saturating_mul(1_000),
+            ],
+            now_millis,
+        )
+    }
+
+    fn from_candidates(candidates: [i64; 3], now_millis: i64) -> Self {
+        let is_plausible = |millis: i64| millis > 0 && millis <= now_millis + MAX_CLOCK_SKEW_MILLIS;
+
+        match candidates.iter().position(|millis| is_plausible(*millis)) {
+            Some(index) => Self {
+                millis: candidates[index],
+                corrected: index > 0,
+            },
+            None => Self {
+                millis: now_millis,
+                corrected: true,
+            },
+        }
+    }
+
+    pub fn to_json(self, precision: TimePrecision) -> serde_json::Value {
+        match precision {
+            TimePrecision::Milliseconds => self.millis.into(),
+            TimePrecision::Seconds => (self.millis / 1_000).into(),
+        }
+    }
+}
+
+/// Current time in milliseconds since the epoch.
+pub fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-07-15T12:00:00Z
+    const NOW: i64 = 1_721_044_800_000;
+
+    #[test]
+    fn uses_milliseconds_when_plausible() {
+        let time = EventTime::from_candidates(
+            [1_721_044_700_123, 1_721_044_700_123, 1_721_044_700_000],
+            NOW,
+        );
+        assert_eq!(
+            time,
+            EventTime {
+                millis: 1_721_044_700_123,
+                corrected: false
+            }
+        );
+        assert_eq!(
+            time.to_json(TimePrecision::Milliseconds),
+            1_721_044_700_123i64
+        );
+        assert_eq!(time.to_json(TimePrecision::Seconds), 1_721_044_700i64);
+    }
+
+    #[test]
+    fn replaces_zero_and_future_timestamps() {
+        let time = EventTime::from_candidates([0, 1_721_044_700_123, 1_721_044_700_000], NOW);
+        assert_eq!(time.millis, 1_721_044_700_123);
+        assert!(time.corrected);
+
+        let far_future = NOW + 24 * 60 * 60 * 1000;
+        let time = EventTime::from_candidates([far_future, 0, 1_721_044_700_000], NOW);
+        assert_eq!(time.millis, 1_721_044_700_000);
+        assert!(time.corrected);
+
+        let time = EventTime::from_candidates([0, 0, 0], NOW);
+        assert_eq!(time.millis, NOW);
+        assert!(time.corrected);
+    }
+
+    #[test]
+    fn parses_precision() {
+        let settings = HashMap::from([("time_precision".to_string(), "Seconds".to_string())]);
+        assert_eq!(
+            TimePrecision::from_settings(&settings).unwrap(),
+            TimePrecision::Seconds
+        );
+        let invalid = HashMap::from([("time_precision".to_string(), "ns".to_string())]);
+        assert!(TimePrecision::from_settings(&invalid).is_err());
+    }
+}
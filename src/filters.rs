@@ -0,0 +1,291 @@
+use crate::exports::edgee::components::data_collection::{Client, PageData};
+use crate::helpers::{parse_bool, parse_list, url_host};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Lowercase user agent fragments of well-known crawlers, monitoring tools and headless browsers.
+/// A bare `bot` only counts at a token boundary (`Googlebot/2.1`, `Foo-Bot`): real devices
+/// such as Cubot phones contain it too.
+const KNOWN_BOT_SIGNATURES: &[&str] = &[
+    "bot/",
+    "bot;",
+    "-bot",
+    "_bot",
+    " bot ",
+    "crawler",
+    "spider",
+    "slurp",
+    "headlesschrome",
+    "phantomjs",
+    "lighthouse",
+    "pingdom",
+    "uptimerobot",
+    "statuscake",
+    "facebookexternalhit",
+    "embedly",
+    "quora link preview",
+    "bingpreview",
+    "python-requests",
+    "python-urllib",
+    "go-http-client",
+    "curl/",
+    "wget/",
+    "okhttp",
+    "axios/",
+    "node-fetch",
+    "java/",
+    "libwww-perl",
+    "scrapy",
+    "httpclient",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterAction {
+    Drop,
+    Tag,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let (address, prefix) = match value.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (value, None),
+        };
+
+        let network: IpAddr = address
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid IP range '{value}'"))?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(prefix) => prefix
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| anyhow::anyhow!("Invalid prefix length in IP range '{value}'"))?,
+            None => max_len,
+        };
+
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrafficClassification {
+    pub is_internal: bool,
+    pub is_bot: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrafficFilter {
+    pub action: FilterAction,
+    pub internal_ip_ranges: Vec<IpRange>,
+    pub internal_user_agents: Vec<String>,
+    pub internal_hostnames: Vec<String>,
+    pub filter_bots: bool,
+}
+
+impl TrafficFilter {
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let action = match settings_map
+            .get("traffic_filter_action")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("tag") => FilterAction::Tag,
+            Some("drop") => FilterAction::Drop,
+            Some(other) => {
+                return Err(anyhow::anyhow!(
+                    "Invalid 'traffic_filter_action' setting: '{other}' (expected 'drop' or 'tag')"
+                ))
+            }
+        };
+
+        let internal_ip_ranges = parse_list(settings_map.get("internal_ip_ranges"))
+            .iter()
+            .map(|range| IpRange::parse(range))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let internal_user_agents = parse_list(settings_map.get("internal_user_agents"))
+            .into_iter()
+            .map(|pattern| pattern.to_lowercase())
+            .collect();
+
+        let internal_hostnames = parse_list(settings_map.get("internal_hostnames"))
+            .into_iter()
+            .map(|hostname| hostname.trim_start_matches('.').to_lowercase())
+            .collect();
+
+        let filter_bots = parse_bool(settings_map.get("filter_bots"), "filter_bots")?;
+
+        Ok(Self {
+            action,
+            internal_ip_ranges,
+            internal_user_agents,
+            internal_hostnames,
+            filter_bots,
+        })
+    }
+
+    pub fn classify(&self, client: &Client, page: &PageData) -> TrafficClassification {
+        let user_agent = client.user_agent.to_lowercase();
+
+        let internal_ip = client
+            .ip
+            .trim()
+            .parse::<IpAddr>()
+            .map(|ip| self.internal_ip_ranges.iter().any(|r| r.contains(&ip)))
+            .unwrap_or(false);
+
+        let internal_user_agent = !user_agent.is_empty()
+            && self
+                .internal_user_agents
+                .iter()
+                .any(|pattern| user_agent.contains(pattern.as_str()));
+
+        let internal_hostname = url_host(&page.url)
+            .map(|host| {
+                self.internal_hostnames
+                    .iter()
+                    .any(|h| host == *h || host.ends_with(&format!(".{h}")))
+            })
+            .unwrap_or(false);
+
+        TrafficClassification {
+            is_internal: internal_ip || internal_user_agent || internal_hostname,
+            is_bot: self.filter_bots && is_known_bot(&user_agent),
+        }
+    }
+
+    /// Drops the event or tags `props` depending on the configured action.
+    pub fn apply(
+        &self,
+        classification: TrafficClassification,
+        props: &mut HashMap<String, String>,
+    ) -> Result<(), String> {
+        if self.action == FilterAction::Drop {
+            if classification.is_internal {
+                return Err("Event dropped: internal traffic".into());
+            }
+            if classification.is_bot {
+                return Err("Event dropped: bot traffic".into());
+            }
+        }
+
+        if classification.is_internal {
+            props.insert("$is_internal".into(), "true".into());
+        }
+        if classification.is_bot {
+            props.insert("$is_bot".into(), "true".into());
+        }
+
+        Ok(())
+    }
+}
+
+pub fn is_known_bot(user_agent: &str) -> bool {
+    let user_agent = user_agent.to_lowercase();
+    KNOWN_BOT_SIGNATURES
+        .iter()
+        .any(|signature| user_agent.contains(signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(settings: &[(&str, &str)]) -> TrafficFilter {
+        let map = settings
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        TrafficFilter::from_settings(&map).unwrap()
+    }
+
+    #[test]
+    fn ip_range_matches_ipv4_and_ipv6() {
+        let range = IpRange::parse("10.1.0.0/16").unwrap();
+        assert!(range.contains(&"10.1.42.7".parse().unwrap()));
+        assert!(!range.contains(&"10.2.0.1".parse().unwrap()));
+
+        let single = IpRange::parse("192.168.0.1").unwrap();
+        assert!(single.contains(&"192.168.0.1".parse().unwrap()));
+        assert!(!single.contains(&"192.168.0.2".parse().unwrap()));
+
+        let v6 = IpRange::parse("2001:db8::/32").unwrap();
+        assert!(v6.contains(&"2001:db8:1::1".parse().unwrap()));
+        assert!(!v6.contains(&"10.1.42.7".parse().unwrap()));
+
+        assert!(IpRange::parse("10.0.0.0/33").is_err());
+        assert!(IpRange::parse("not-an-ip").is_err());
+    }
+
+    #[test]
+    fn known_bots_are_detected() {
+        assert!(is_known_bot(
+            "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)"
+        ));
+        assert!(is_known_bot("curl/8.4.0"));
+        assert!(is_known_bot(
+            "Mozilla/5.0 (compatible; archive.org_bot +http://archive.org/details/archive.org_bot)"
+        ));
+        assert!(!is_known_bot(
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Safari/605.1.15"
+        ));
+        assert!(!is_known_bot(
+            "Mozilla/5.0 (Linux; Android 10; CUBOT X30) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36"
+        ));
+        assert!(!is_known_bot("Shop/4.2 CFNetwork/1474 Darwin/23.0.0"));
+    }
+
+    #[test]
+    fn invalid_action_is_rejected() {
+        let map = HashMap::from([("traffic_filter_action".to_string(), "ignore".to_string())]);
+        assert!(TrafficFilter::from_settings(&map).is_err());
+    }
+
+    #[test]
+    fn apply_drops_or_tags() {
+        let classification = TrafficClassification {
+            is_internal: true,
+            is_bot: false,
+        };
+
+        let mut props = HashMap::new();
+        let drop = filter(&[("traffic_filter_action", "drop")]);
+        assert!(drop.apply(classification, &mut props).is_err());
+
+        let tag = filter(&[("traffic_filter_action", "tag")]);
+        assert!(tag.apply(classification, &mut props).is_ok());
+        assert_eq!(props.get("$is_internal"), Some(&"true".to_string()));
+        assert!(!props.contains_key("$is_bot"));
+    }
+}
@@ -1,4 +1,3 @@
-use regex::Regex;
 use std::collections::HashMap;
 
 pub fn insert_if_nonempty(map: &mut HashMap<String, String>, key: &str, value: &str) {
@@ -7,29 +6,106 @@ pub fn insert_if_nonempty(map: &mut HashMap<String, String>, key: &str, value: &
     }
 }
 
-pub fn parse_browser_info(user_agent: &str) -> (Option<String>, Option<String>) {
-    let browser_regexes = vec![
-        (r"(Firefox)/([\d\.]+)", "Firefox"),
-        (r"(Edg)/([\d\.]+)", "Edge"),
-        (r"(Chrome)/([\d\.]+)", "Chrome"),
-        (r"(Safari)/([\d\.]+)", "Safari"),
-        (r"(Opera)/([\d\.]+)", "Opera"),
-    ];
+pub fn parse_list(value: Option<&String>) -> Vec<String> {
+    value
+        .map(|v| {
+            v.split([',', '\n'])
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn parse_bool(value: Option<&String>, key: &str) -> anyhow::Result<bool> {
+    match value.map(|v| v.trim().to_lowercase()).as_deref() {
+        None | Some("") => Ok(false),
+        Some("true" | "1" | "yes" | "on") => Ok(true),
+        Some("false" | "0" | "no" | "off") => Ok(false),
+        Some(other) => Err(anyhow::anyhow!(
+            "Invalid '{key}' setting: '{other}' is not a boolean"
+        )),
+    }
+}
 
-    for (pattern, name) in browser_regexes {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(caps) = re.captures(user_agent) {
-                let version = caps.get(2).map(|m| m.as_str().to_string());
-                return (Some(name.to_string()), version);
+pub fn url_host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = if host.starts_with('[') {
+        host.split(']').next()?.trim_start_matches('[')
+    } else {
+        host.split(':').next()?
+    };
+
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_lowercase())
+    }
+}
+
+/// Returns the decoded `key=value` pairs of the query string of `url`,
+/// which may be a full URL or a bare search string such as `?a=1&b=2`.
+pub fn parse_query(url: &str) -> Vec<(String, String)> {
+    let without_fragment = url.split('#').next().unwrap_or_default();
+    let query = match without_fragment.split_once('?') {
+        Some((_, query)) => query,
+        None => return Vec::new(),
+    };
+
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decodes `application/x-www-form-urlencoded` text; invalid escapes are kept verbatim.
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high << 4 | low);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
             }
+            byte => decoded.push(byte),
         }
+        i += 1;
     }
 
-    (None, None)
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Encodes `value` for a URL query string, keeping only unreserved characters.
+pub fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
 }
 
-pub fn mixpanel_endpoint(region: &str) -> String {
-    format!("https://{region}.mixpanel.com")
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
 }
 
 #[cfg(test)]
@@ -47,17 +123,57 @@ mod tests {
     }
 
     #[test]
-    fn parse_browser_info_detects_chrome() {
-        let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
-        let (name, version) = parse_browser_info(ua);
-        assert_eq!(name, Some("Chrome".to_string()));
-        assert_eq!(version, Some("120.0.0.0".to_string()));
+    fn parse_list_splits_and_trims() {
+        let value = "10.0.0.0/8, 192.168.1.1\n,  ".to_string();
+        assert_eq!(
+            parse_list(Some(&value)),
+            vec!["10.0.0.0/8".to_string(), "192.168.1.1".to_string()]
+        );
+        assert!(parse_list(None).is_empty());
+    }
+
+    #[test]
+    fn parse_bool_accepts_common_values() {
+        assert!(parse_bool(Some(&"true".to_string()), "key").unwrap());
+        assert!(parse_bool(Some(&"On".to_string()), "key").unwrap());
+        assert!(!parse_bool(Some(&"0".to_string()), "key").unwrap());
+        assert!(!parse_bool(None, "key").unwrap());
+        assert!(parse_bool(Some(&"maybe".to_string()), "key").is_err());
+    }
+
+    #[test]
+    fn percent_encode_round_trips() {
+        assert_eq!(percent_encode("a&b=c d/é"), "a%26b%3Dc%20d%2F%C3%A9");
+        assert_eq!(percent_encode("project-42_x.y~"), "project-42_x.y~");
+        assert_eq!(percent_decode(&percent_encode("a&b=c+d")), "a&b=c+d");
+    }
+
+    #[test]
+    fn parse_query_decodes_pairs() {
+        assert_eq!(
+            parse_query("https://www.google.com/search?q=edgee+mixpanel%21&hl=fr#top"),
+            vec![
+                ("q".to_string(), "edgee mixpanel!".to_string()),
+                ("hl".to_string(), "fr".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_query("?flag&bad=%zz"),
+            vec![
+                ("flag".to_string(), "".to_string()),
+                ("bad".to_string(), "%zz".to_string()),
+            ]
+        );
+        assert!(parse_query("https://example.com/").is_empty());
     }
 
     #[test]
-    fn mixpanel_endpoint_works() {
-        let region = "api-eu";
-        let endpoint = mixpanel_endpoint(region);
-        assert_eq!(endpoint, "https://api-eu.mixpanel.com");
+    fn url_host_extracts_hostname() {
+        assert_eq!(
+            url_host("https://user@Shop.Example.com:8080/path?q=1"),
+            Some("shop.example.com".to_string())
+        );
+        assert_eq!(url_host("http://[::1]:3000/"), Some("::1".to_string()));
+        assert_eq!(url_host("/relative/path"), None);
     }
 }
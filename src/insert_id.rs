@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+/// Mixpanel only deduplicates on `$insert_id`s of at most 36 alphanumeric or dash characters.
+pub const MAX_LEN: usize = 36;
+
+/// Returns a Mixpanel-compliant `$insert_id` for an event. Characters other
+/// than ASCII alphanumerics and dashes are removed from `uuid`; when nothing
+/// is left, a hash of the event's name, distinct_id, timestamp and properties
+/// is used so retries of the same event still deduplicate. `uuid` is the
+/// `$insert_id` sent with the event, or the Edgee event uuid. Requests carry a
+/// single event, so ids are never suffixed for fanned-out events.
+pub fn resolve(
+    uuid: &str,
+    name: &str,
+    distinct_id: &str,
+    timestamp_millis: i64,
+    properties: &HashMap<String, String>,
+) -> String {
+    let sanitized: String = uuid
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+
+    match sanitized.len() {
+        0 => {
+            let mut keys: Vec<&String> = properties.keys().collect();
+            keys.sort();
+
+            let mut fingerprint = format!("{name}\0{distinct_id}\0{timestamp_millis}");
+            for key in keys {
+                fingerprint.push('\0');
+                fingerprint.push_str(key);
+                fingerprint.push('=');
+                fingerprint.push_str(&properties[key]);
+            }
+            hash(&fingerprint)
+        }
+        len if len > MAX_LEN => hash(&sanitized),
+        _ => sanitized,
+    }
+}
+
+/// 128-bit FNV-1a, as 32 hexadecimal characters.
+fn hash(value: &str) -> String {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    let hash = value.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u128::from(byte)).wrapping_mul(PRIME)
+    });
+    format!("{hash:032x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_compliant(id: &str) -> bool {
+        !id.is_empty()
+            && id.len() <= MAX_LEN
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    }
+
+    #[test]
+    fn sanitizes_uuids() {
+        let props = HashMap::new();
+        assert_eq!(
+            resolve(
+                "0b5f1e7c-6a9d-4c36-9f0e-1f2d3c4b5a69",
+                "Signup",
+                "u1",
+                0,
+                &props
+            ),
+            "0b5f1e7c-6a9d-4c36-9f0e-1f2d3c4b5a69"
+        );
+        assert_eq!(
+            resolve("{0b5f1e7c-6a9d}", "Signup", "u1", 0, &props),
+            "0b5f1e7c-6a9d"
+        );
+
+        let long = resolve(&"a".repeat(40), "Signup", "u1", 0, &props);
+        assert!(is_compliant(&long));
+        assert_eq!(long, resolve(&"a".repeat(40), "Other", "u2", 1, &props));
+    }
+
+    #[test]
+    fn falls_back_to_a_deterministic_hash() {
+        let props = HashMap::from([
+            ("plan".to_string(), "pro".to_string()),
+            ("seats".to_string(), "3".to_string()),
+        ]);
+        let id = resolve("", "Signup", "u1", 1_721_044_700_123, &props);
+        assert!(is_compliant(&id));
+        assert_eq!(id, resolve(" ", "Signup", "u1", 1_721_044_700_123, &props));
+        assert_ne!(id, resolve("", "Signup", "u2", 1_721_044_700_123, &props));
+        assert_ne!(id, resolve("", "Signup", "u1", 1_721_044_700_124, &props));
+    }
+}
@@ -1,11 +1,43 @@
+mod api;
+mod channel;
+mod click_ids;
+mod enrichment;
+mod event_time;
+mod filters;
 mod helpers;
+mod insert_id;
+mod limits;
+mod locale;
+mod mixpanel_js;
+mod namespace;
+mod precedence;
+mod referrer;
+mod super_properties;
+mod tracking_plan;
+mod transport;
+mod url;
+mod user_agent;
+mod utm;
 use crate::exports::edgee::components::data_collection::Data;
 use crate::exports::edgee::components::data_collection::{Dict, EdgeeRequest, Event, HttpMethod};
-use base64::engine::general_purpose::STANDARD;
-use base64::Engine;
+use api::{ApiHost, ApiOptions, ImportAuth, IngestionMode, Region};
+use channel::{ChannelRules, Touchpoint};
+use enrichment::{ContextGroups, EnrichmentToggles};
+use event_time::{EventTime, TimePrecision};
 use exports::edgee::components::data_collection::Guest;
-use helpers::{insert_if_nonempty, mixpanel_endpoint, parse_browser_info};
-use std::collections::HashMap;
+use filters::TrafficFilter;
+use helpers::{insert_if_nonempty, parse_bool};
+use limits::PayloadLimits;
+use mixpanel_js::PropertyNaming;
+use namespace::ContextNamespace;
+use precedence::PropertyPrecedence;
+use referrer::Referrer;
+use std::collections::{HashMap, HashSet};
+use super_properties::SuperProperties;
+use tracking_plan::TrackingPlan;
+use transport::{BodyCompression, EdgeeHost};
+use url::{QueryStripper, UrlParts};
+use utm::{UtmNaming, UtmParams};
 
 wit_bindgen::generate!({world: "data-collection", path: ".edgee/wit", generate_all});
 export!(Component);
@@ -25,48 +57,85 @@ struct Component;
 */
 
 impl Guest for Component {
-    fn page(edgee_event: Event, settings_dict: Dict) -> Result<EdgeeRequest, String> {
+    fn page(mut edgee_event: Event, settings_dict: Dict) -> Result<EdgeeRequest, String> {
         let settings = Settings::new(settings_dict).map_err(|e| e.to_string())?;
+        sanitize_event(&mut edgee_event, &settings);
 
-        let mut props = HashMap::new();
+        let mut event_props = HashMap::new();
 
         if let Data::Page(ref data) = edgee_event.data {
             for (k, v) in &data.properties {
-                insert_if_nonempty(&mut props, k, v);
+                insert_if_nonempty(&mut event_props, k, v);
             }
 
-            enrich_with_page_context(&mut props, &edgee_event.context.page);
-            enrich_with_campaign_context(&mut props, &edgee_event.context.campaign);
-            enrich_with_session_context(&mut props, &edgee_event.context.session);
-            enrich_with_client_context(&mut props, &edgee_event.context.client);
-
-            return build_mixpanel_request(&edgee_event, &settings, "Page View", props);
+            let name = settings.property_naming.page_view_event();
+            apply_tracking_plan(
+                &settings,
+                name,
+                &mut event_props,
+                &mut edgee_event.context.page,
+            )?;
+
+            let mut context_props = HashMap::new();
+            enrich_with_context(
+                &mut context_props,
+                &edgee_event,
+                &settings,
+                settings.enrichment.page,
+            );
+            let event_keys: HashSet<String> = event_props.keys().cloned().collect();
+            let mut props = merge_properties(&settings, event_props, context_props);
+            settings.super_properties.apply(&mut props);
+            apply_traffic_filter(&mut props, &edgee_event, &settings)?;
+
+            let request = build_mixpanel_request(&edgee_event, &settings, name, props)?;
+            let request = settings.payload_limits.enforce(request, &event_keys)?;
+            return Ok(settings.compression.apply(request, &EdgeeHost));
         }
 
         Err("Invalid event type for page".into())
     }
 
-    fn track(edgee_event: Event, settings_dict: Dict) -> Result<EdgeeRequest, String> {
+    fn track(mut edgee_event: Event, settings_dict: Dict) -> Result<EdgeeRequest, String> {
         let settings = Settings::new(settings_dict).map_err(|e| e.to_string())?;
-        let mut props = HashMap::new();
+        sanitize_event(&mut edgee_event, &settings);
+        let mut event_props = HashMap::new();
 
         if let Data::Track(ref data) = edgee_event.data {
             for (k, v) in &data.properties {
-                insert_if_nonempty(&mut props, k, v);
+                insert_if_nonempty(&mut event_props, k, v);
             }
 
-            enrich_with_page_context(&mut props, &edgee_event.context.page);
-            enrich_with_campaign_context(&mut props, &edgee_event.context.campaign);
-            enrich_with_session_context(&mut props, &edgee_event.context.session);
-            enrich_with_client_context(&mut props, &edgee_event.context.client);
-            return build_mixpanel_request(&edgee_event, &settings, &data.name, props);
+            apply_tracking_plan(
+                &settings,
+                &data.name,
+                &mut event_props,
+                &mut edgee_event.context.page,
+            )?;
+
+            let mut context_props = HashMap::new();
+            enrich_with_context(
+                &mut context_props,
+                &edgee_event,
+                &settings,
+                settings.enrichment.track,
+            );
+            let event_keys: HashSet<String> = event_props.keys().cloned().collect();
+            let mut props = merge_properties(&settings, event_props, context_props);
+            settings.super_properties.apply(&mut props);
+            apply_traffic_filter(&mut props, &edgee_event, &settings)?;
+
+            let request = build_mixpanel_request(&edgee_event, &settings, &data.name, props)?;
+            let request = settings.payload_limits.enforce(request, &event_keys)?;
+            return Ok(settings.compression.apply(request, &EdgeeHost));
         }
 
         Err("Invalid event type for track".into())
     }
 
-    fn user(edgee_event: Event, settings_dict: Dict) -> Result<EdgeeRequest, String> {
+    fn user(mut edgee_event: Event, settings_dict: Dict) -> Result<EdgeeRequest, String> {
         let settings = Settings::new(settings_dict).map_err(|e| e.to_string())?;
+        sanitize_event(&mut edgee_event, &settings);
         let user = &edgee_event.context.user;
         let client = &edgee_event.context.client;
 
@@ -76,29 +145,69 @@ impl Guest for Component {
             user.user_id.clone()
         };
 
-        let mut props = HashMap::new();
-        props.insert("$distinct_id".into(), distinct_id.clone());
-        props.insert("$user_id".into(), distinct_id.clone());
-        insert_if_nonempty(&mut props, "$ip", &client.ip);
+        let mut user_props = HashMap::new();
+        user_props.insert("$distinct_id".into(), distinct_id.clone());
+        user_props.insert("$user_id".into(), distinct_id.clone());
+        insert_if_nonempty(&mut user_props, "$ip", &client.ip);
 
         for (k, v) in &user.properties {
-            insert_if_nonempty(&mut props, k, v);
+            insert_if_nonempty(&mut user_props, k, v);
         }
 
-        enrich_with_page_context(&mut props, &edgee_event.context.page);
-        enrich_with_campaign_context(&mut props, &edgee_event.context.campaign);
-        enrich_with_session_context(&mut props, &edgee_event.context.session);
-        enrich_with_client_context(&mut props, client);
+        let mut context_props = HashMap::new();
+        match settings.property_naming {
+            PropertyNaming::Edgee => enrich_with_context(
+                &mut context_props,
+                &edgee_event,
+                &settings,
+                settings.enrichment.profile,
+            ),
+            PropertyNaming::MixpanelJs => {
+                mixpanel_js::enrich_profile(&mut context_props, &edgee_event)
+            }
+        }
+        let user_keys: HashSet<String> = user_props.keys().cloned().collect();
+        let mut props = merge_properties(&settings, user_props, context_props);
+        if settings.super_properties.on_profile {
+            settings.super_properties.apply(&mut props);
+        }
+        apply_traffic_filter(&mut props, &edgee_event, &settings)?;
 
-        build_mixpanel_user_request(&settings, distinct_id, props)
+        let set_once_props = if settings.click_ids_on_profile {
+            click_ids::first_touch_properties(&edgee_event.context.page)
+        } else {
+            HashMap::new()
+        };
+
+        let request = build_mixpanel_user_request(&settings, distinct_id, props, set_once_props)?;
+        settings.payload_limits.enforce(request, &user_keys)
     }
 }
 
 pub struct Settings {
-    pub api_secret: String,
+    pub import_auth: Option<ImportAuth>,
     pub project_token: String,
     pub project_id: Option<String>,
-    pub region: String,
+    pub region: Region,
+    pub api_host: ApiHost,
+    pub api_options: ApiOptions,
+    pub traffic_filter: TrafficFilter,
+    pub property_naming: PropertyNaming,
+    pub lib_version: String,
+    pub channel_rules: ChannelRules,
+    pub utm_naming: UtmNaming,
+    pub click_ids_on_profile: bool,
+    pub query_stripper: QueryStripper,
+    pub super_properties: SuperProperties,
+    pub property_precedence: PropertyPrecedence,
+    pub report_property_collisions: bool,
+    pub context_namespace: ContextNamespace,
+    pub enrichment: EnrichmentToggles,
+    pub time_precision: TimePrecision,
+    pub payload_limits: PayloadLimits,
+    pub tracking_plan: Option<TrackingPlan>,
+    pub ingestion_mode: IngestionMode,
+    pub compression: BodyCompression,
 }
 
 impl Settings {
@@ -108,11 +217,14 @@ impl Settings {
             .map(|(key, value)| (key.to_string(), value.to_string()))
             .collect();
 
-        let api_secret = settings_map
-            .get("api_secret")
-            .filter(|t| !t.trim().is_empty())
-            .ok_or_else(|| anyhow::anyhow!("Missing or empty 'api_secret' setting"))?
-            .to_string();
+        let ingestion_mode = IngestionMode::from_settings(&settings_map)?;
+
+        let import_auth = ImportAuth::from_settings(&settings_map, ingestion_mode)?;
+        if import_auth.is_none() && ingestion_mode == IngestionMode::Import {
+            return Err(anyhow::anyhow!(
+                "Missing or empty 'api_secret' setting (required when 'ingestion_mode' is 'import', unless 'service_account_username' and 'service_account_secret' are set)"
+            ));
+        }
 
         let project_token = settings_map
             .get("project_token")
@@ -120,34 +232,218 @@ impl Settings {
             .ok_or_else(|| anyhow::anyhow!("Missing or empty 'project_token' setting"))?
             .to_string();
 
-        let project_id = settings_map.get("project_id").cloned();
-
-        let region = settings_map
-            .get("region")
-            .cloned()
-            .unwrap_or_else(|| "api".to_string());
+        let project_id = settings_map
+            .get("project_id")
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty());
+
+        let region = Region::from_settings(&settings_map)?;
+        let api_host = ApiHost::from_settings(&settings_map, region)?;
+        let api_options = ApiOptions::from_settings(&settings_map)?;
+
+        let traffic_filter = TrafficFilter::from_settings(&settings_map)?;
+        let property_naming = PropertyNaming::from_settings(&settings_map)?;
+        let lib_version = settings_map
+            .get("lib_version")
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .unwrap_or(mixpanel_js::DEFAULT_LIB_VERSION)
+            .to_string();
+        let channel_rules = ChannelRules::from_settings(&settings_map)?;
+        let utm_naming = UtmNaming::from_settings(&settings_map)?;
+        let click_ids_on_profile = parse_bool(
+            settings_map.get("click_ids_on_profile"),
+            "click_ids_on_profile",
+        )?;
+
+        let query_stripper = QueryStripper::from_settings(&settings_map);
+        let super_properties = SuperProperties::from_settings(&settings_map)?;
+        let property_precedence = PropertyPrecedence::from_settings(&settings_map)?;
+        let report_property_collisions = parse_bool(
+            settings_map.get("report_property_collisions"),
+            "report_property_collisions",
+        )?;
+        let context_namespace = ContextNamespace::from_settings(&settings_map)?;
+        let enrichment = EnrichmentToggles::from_settings(&settings_map)?;
+        let time_precision = TimePrecision::from_settings(&settings_map)?;
+        let payload_limits = PayloadLimits::from_settings(&settings_map)?;
+        let tracking_plan = TrackingPlan::from_settings(&settings_map)?;
 
         Ok(Self {
-            api_secret,
+            import_auth,
             project_token,
             project_id,
             region,
+            api_host,
+            api_options,
+            traffic_filter,
+            property_naming,
+            lib_version,
+            channel_rules,
+            utm_naming,
+            click_ids_on_profile,
+            query_stripper,
+            super_properties,
+            property_precedence,
+            report_property_collisions,
+            context_namespace,
+            enrichment,
+            time_precision,
+            payload_limits,
+            tracking_plan,
+            ingestion_mode,
+            compression: BodyCompression::default(),
         })
     }
 }
 
+fn sanitize_event(event: &mut Event, settings: &Settings) {
+    settings
+        .query_stripper
+        .sanitize_page(&mut event.context.page);
+    if let Data::Page(ref mut data) = event.data {
+        settings.query_stripper.sanitize_page(data);
+    }
+}
+
+fn enrich_with_context(
+    props: &mut HashMap<String, String>,
+    event: &Event,
+    settings: &Settings,
+    groups: ContextGroups,
+) {
+    match settings.property_naming {
+        PropertyNaming::Edgee => {
+            let namespace = settings.context_namespace;
+
+            if groups.page {
+                let mut page_props = HashMap::new();
+                enrich_with_page_context(&mut page_props, &event.context.page);
+                namespace.extend(props, "page", page_props);
+            }
+
+            if groups.campaign {
+                let mut campaign_props = HashMap::new();
+                enrich_with_campaign_context(&mut campaign_props, event, settings);
+                namespace.extend(props, "campaign", campaign_props);
+            }
+
+            if groups.session {
+                let mut session_props = HashMap::new();
+                enrich_with_session_context(&mut session_props, &event.context.session);
+                namespace.extend(props, "session", session_props);
+            }
+
+            if groups.client {
+                let mut client_props = HashMap::new();
+                enrich_with_client_context(
+                    &mut client_props,
+                    &event.context.client,
+                    event.timestamp,
+                );
+                namespace.extend(props, "client", client_props);
+            }
+        }
+        PropertyNaming::MixpanelJs => {
+            mixpanel_js::enrich_event(props, event, &settings.lib_version)
+        }
+    }
+}
+
+/// Validates the properties supplied by the sender, the event's own and those of
+/// `context.page.properties` (the event's win), against the tracking plan.
+/// Properties stripped by the plan are removed from both sources, so that page
+/// context enrichment cannot add them back.
+fn apply_tracking_plan(
+    settings: &Settings,
+    name: &str,
+    event_props: &mut HashMap<String, String>,
+    page: &mut crate::exports::edgee::components::data_collection::PageData,
+) -> Result<(), String> {
+    let Some(plan) = &settings.tracking_plan else {
+        return Ok(());
+    };
+
+    let mut props = HashMap::new();
+    for (k, v) in &page.properties {
+        insert_if_nonempty(&mut props, k, v);
+    }
+    props.extend(event_props.clone());
+    plan.apply(name, &mut props)?;
+
+    event_props.retain(|key, _| props.contains_key(key));
+    page.properties.retain(|(key, _)| props.contains_key(key));
+    if let Some(report) = props.remove("$schema_violations") {
+        event_props.insert("$schema_violations".into(), report);
+    }
+
+    Ok(())
+}
+
+/// Merges context enrichment into the event's own properties following the
+/// `property_precedence` setting. When `report_property_collisions` is set,
+/// the keys whose values collided are reported in `property_collisions`.
+fn merge_properties(
+    settings: &Settings,
+    event_props: HashMap<String, String>,
+    context_props: HashMap<String, String>,
+) -> HashMap<String, String> {
+    let (mut props, collisions) = settings
+        .property_precedence
+        .merge(event_props, context_props);
+
+    if settings.report_property_collisions && !collisions.is_empty() {
+        if let Ok(report) = serde_json::to_string(&collisions) {
+            props.insert("property_collisions".into(), report);
+        }
+    }
+
+    props
+}
+
+fn apply_traffic_filter(
+    props: &mut HashMap<String, String>,
+    event: &Event,
+    settings: &Settings,
+) -> Result<(), String> {
+    let classification = settings
+        .traffic_filter
+        .classify(&event.context.client, &event.context.page);
+    settings.traffic_filter.apply(classification, props)
+}
+
 fn enrich_with_client_context(
     props: &mut HashMap<String, String>,
     client: &crate::exports::edgee::components::data_collection::Client,
+    timestamp: i64,
 ) {
-    let (browser_name, browser_version) = parse_browser_info(&client.user_agent);
+    let user_agent = user_agent::parse_client(client);
 
-    if let Some(name) = browser_name {
+    if let Some(device) = user_agent.device_name() {
+        props.insert("$device".into(), device);
+    }
+    if let Some(name) = user_agent.browser {
         props.insert("$browser".into(), name);
     }
-    if let Some(version) = browser_version {
+    if let Some(version) = user_agent.browser_version {
         props.insert("$browser_version".into(), version);
     }
+    if let Some(os) = user_agent.os {
+        props.insert("$os".into(), os);
+    }
+    if let Some(os_version) = user_agent.os_version {
+        props.insert("$os_version".into(), os_version);
+    }
+    if let Some(vendor) = user_agent.device_vendor {
+        props.insert("$manufacturer".into(), vendor);
+    }
+    if let Some(model) = user_agent.device_model {
+        props.insert("$model".into(), model);
+    }
+    props.insert(
+        "device_type".into(),
+        user_agent.device_type.as_str().to_string(),
+    );
 
     insert_if_nonempty(props, "ip", &client.ip);
     insert_if_nonempty(props, "$city", &client.city);
@@ -157,7 +453,8 @@ fn enrich_with_client_context(
     insert_if_nonempty(props, "country_name", &client.country_name);
     insert_if_nonempty(props, "continent", &client.continent);
     insert_if_nonempty(props, "locale", &client.locale);
-    insert_if_nonempty(props, "$timezone", &client.timezone);
+    locale::enrich_locale(props, &client.locale);
+    locale::enrich_timezone(props, &client.timezone, timestamp);
     insert_if_nonempty(props, "$os", &client.os_name);
     insert_if_nonempty(props, "$os_version", &client.os_version);
     insert_if_nonempty(props, "user_agent", &client.user_agent);
@@ -192,15 +489,22 @@ fn enrich_with_page_context(
     page: &crate::exports::edgee::components::data_collection::PageData,
 ) {
     insert_if_nonempty(props, "$current_url", &page.url);
+
+    let mut url_parts = UrlParts::parse(&page.url);
+    if url_parts.query.is_empty() {
+        url_parts.query = page.search.trim_start_matches('?').to_string();
+    }
+    url_parts.enrich(props);
     insert_if_nonempty(props, "path", &page.path);
     insert_if_nonempty(props, "title", &page.title);
     insert_if_nonempty(props, "category", &page.category);
     insert_if_nonempty(props, "name", &page.name);
     insert_if_nonempty(props, "$referrer", &page.referrer);
+    Referrer::parse(&page.referrer).enrich(props);
 
     if !page.keywords.is_empty() {
         if let Ok(serialized_keywords) = serde_json::to_string(&page.keywords) {
-            props.insert("mp_keyword".into(), serialized_keywords);
+            props.insert("keywords".into(), serialized_keywords);
         }
     }
 
@@ -211,15 +515,25 @@ fn enrich_with_page_context(
 
 fn enrich_with_campaign_context(
     props: &mut HashMap<String, String>,
-    campaign: &crate::exports::edgee::components::data_collection::Campaign,
+    event: &Event,
+    settings: &Settings,
 ) {
-    insert_if_nonempty(props, "utm_name", &campaign.name);
-    insert_if_nonempty(props, "utm_source", &campaign.source);
-    insert_if_nonempty(props, "utm_medium", &campaign.medium);
-    insert_if_nonempty(props, "utm_term", &campaign.term);
-    insert_if_nonempty(props, "utm_content", &campaign.content);
-    insert_if_nonempty(props, "utm_creative_format", &campaign.creative_format);
-    insert_if_nonempty(props, "utm_marketing_tactic", &campaign.marketing_tactic);
+    let page = &event.context.page;
+    click_ids::enrich(props, page);
+
+    let utm = UtmParams::resolve(&event.context.campaign, page);
+    utm.enrich(props, settings.utm_naming);
+
+    let touchpoint = Touchpoint::new(
+        &utm,
+        page,
+        &Referrer::parse(&page.referrer),
+        &settings.traffic_filter.internal_hostnames,
+    );
+    props.insert(
+        "channel".into(),
+        settings.channel_rules.classify(&touchpoint),
+    );
 }
 
 fn enrich_with_session_context(
@@ -234,6 +548,9 @@ fn enrich_with_session_context(
     props.insert("$last_seen".into(), session.last_seen.to_string());
 }
 
+/// Builds the `/import` or `/track` request of an event. Each request carries
+/// exactly one event, so `$insert_id`s never need the position suffixes that
+/// events fanned out of a single one would.
 fn build_mixpanel_request(
     event: &Event,
     settings: &Settings,
@@ -249,21 +566,40 @@ fn build_mixpanel_request(
         user.user_id.clone()
     };
 
-    props.insert(
-        "$mp_api_endpoint".into(),
-        mixpanel_endpoint(&settings.region).into(),
-    );
-    props.insert("$import".into(), serde_json::json!(true));
-    props.insert("token".into(), settings.api_secret.clone().into());
+    let token = match (settings.ingestion_mode, &settings.import_auth) {
+        (IngestionMode::Import, Some(ImportAuth::ApiSecret(api_secret))) => api_secret,
+        _ => &settings.project_token,
+    };
+    if settings.ingestion_mode == IngestionMode::Import {
+        props.insert("$import".into(), serde_json::json!(true));
+    }
+    props.insert("token".into(), token.clone().into());
     props.insert("$distinct_id".into(), distinct_id.clone().into());
     props.insert("$user_id".into(), distinct_id.clone().into());
-    props.insert("distinct_id".into(), distinct_id.into());
-    props.insert("time".into(), serde_json::json!(event.timestamp));
-    props.insert("$insert_id".into(), serde_json::json!(event.uuid.clone()));
-
-    for (k, v) in properties {
-        props.insert(k, v.into());
+    props.insert("distinct_id".into(), distinct_id.clone().into());
+    let time = EventTime::resolve(event, event_time::now_millis());
+    props.insert("time".into(), time.to_json(settings.time_precision));
+    if time.corrected {
+        props.insert("time_corrected".into(), serde_json::json!(true));
+        props.insert(
+            "original_timestamp".into(),
+            serde_json::json!(event.timestamp_millis),
+        );
     }
+    // An `$insert_id` sent with the event wins over the Edgee event uuid.
+    let id = insert_id::resolve(
+        properties
+            .get("$insert_id")
+            .map(String::as_str)
+            .unwrap_or(&event.uuid),
+        name,
+        &distinct_id,
+        event.timestamp_millis,
+        &properties,
+    );
+
+    props.extend(settings.context_namespace.to_json(properties));
+    props.insert("$insert_id".into(), id.into());
 
     let event_obj = serde_json::json!({
         "event": name,
@@ -272,22 +608,34 @@ fn build_mixpanel_request(
 
     let payload = serde_json::json!([event_obj]);
 
-    let mut url = format!("https://{}.mixpanel.com/import?strict=1", settings.region);
-    if let Some(id) = &settings.project_id {
-        url.push_str(&format!("&project_id={id}"));
-    }
-
-    let encoded = STANDARD.encode(format!("{}:", settings.api_secret).as_bytes());
-    let auth = format!("Basic {encoded}");
+    let mut headers = vec![
+        ("Content-Type".into(), "application/json".into()),
+        ("Accept".into(), "application/json".into()),
+    ];
+    let url = match (settings.ingestion_mode, &settings.import_auth) {
+        (IngestionMode::Import, Some(auth)) => {
+            headers.push(("Authorization".into(), auth.authorization()));
+
+            let mut url = settings
+                .api_host
+                .url(IngestionMode::Import.path())
+                .param_if(settings.api_options.strict, "strict", "1");
+            if let Some(id) = &settings.project_id {
+                url = url.param("project_id", id);
+            }
+            url.build()
+        }
+        _ => settings
+            .api_host
+            .url(IngestionMode::Track.path())
+            .param_if(settings.api_options.verbose, "verbose", "1")
+            .build(),
+    };
 
     Ok(EdgeeRequest {
         method: HttpMethod::Post,
         url,
-        headers: vec![
-            ("Content-Type".into(), "application/json".into()),
-            ("Accept".into(), "application/json".into()),
-            ("Authorization".into(), auth),
-        ],
+        headers,
         body: payload.to_string(),
         forward_client_headers: false,
     })
@@ -297,24 +645,26 @@ fn build_mixpanel_user_request(
     settings: &Settings,
     distinct_id: String,
     props: HashMap<String, String>,
+    set_once_props: HashMap<String, String>,
 ) -> Result<EdgeeRequest, String> {
-    let mut set_props: serde_json::Map<String, serde_json::Value> = props
-        .into_iter()
-        .map(|(k, v)| (k, serde_json::Value::String(v)))
-        .collect();
-
-    set_props.insert(
-        "$mp_api_endpoint".into(),
-        mixpanel_endpoint(&settings.region).into(),
-    );
+    let set_props = settings.context_namespace.to_json(props);
 
-    let payload = serde_json::json!([{
+    let mut update = serde_json::json!({
         "$distinct_id": distinct_id,
         "$token": settings.project_token,
         "$set": set_props
-    }]);
+    });
+    if !set_once_props.is_empty() {
+        update["$set_once"] = serde_json::json!(set_once_props);
+    }
+
+    let payload = serde_json::json!([update]);
 
-    let url = format!("https://{}.mixpanel.com/engage", settings.region);
+    let url = settings
+        .api_host
+        .url("engage")
+        .param_if(settings.api_options.verbose, "verbose", "1")
+        .build();
 
     Ok(EdgeeRequest {
         method: HttpMethod::Post,
@@ -489,6 +839,9 @@ mod tests {
         assert!(req.url.contains("project_id=7891011"));
         assert!(req.body.contains("\"event\":\"Signup\""));
         assert!(req.body.contains("\"token\":\"abc123\""));
+        assert!(req.body.contains("\"channel\":\"Unassigned\""));
+        assert!(req.body.contains("\"utm_campaign\":\"random\""));
+        assert!(!req.body.contains("utm_name"));
     }
 
     #[test]
@@ -515,5 +868,643 @@ mod tests {
         assert!(req.body.contains("\"event\":\"Page View\""));
         assert!(req.body.contains("\"token\":\"abc123\""));
         assert!(req.body.contains("\"$current_url\""));
+        assert!(req.body.contains("\"$referring_domain\":\"example.com\""));
+        assert!(req.body.contains("\"keywords\""));
+        assert!(!req.body.contains("mp_keyword"));
+        assert!(req.body.contains("\"language\":\"fr\""));
+        assert!(req.body.contains("\"$timezone\":\"Etc/GMT-1\""));
+        assert!(req.body.contains("\"timezone_raw\":\"CET\""));
+        assert!(req.body.contains("\"timezone_utc_offset\":\"+01:00\""));
+    }
+
+    #[test]
+    fn internal_traffic_is_dropped_or_tagged() {
+        let event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-789".to_string(),
+            "fr".to_string(),
+            true,
+        );
+
+        let mut settings = vec![
+            ("api_secret".to_string(), "abc123".to_string()),
+            ("project_token".to_string(), "tok123".to_string()),
+            (
+                "internal_ip_ranges".to_string(),
+                "192.168.0.0/24".to_string(),
+            ),
+            ("traffic_filter_action".to_string(), "drop".to_string()),
+        ];
+        let result = Component::page(event.clone(), settings.clone());
+        assert!(result.is_err());
+
+        settings[3].1 = "tag".to_string();
+        let req = Component::page(event, settings).unwrap();
+        assert!(req.body.contains("\"$is_internal\":\"true\""));
+        assert!(!req.body.contains("$is_bot"));
+    }
+
+    #[test]
+    fn mixpanel_js_naming_matches_browser_sdk() {
+        let event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-789".to_string(),
+            "fr".to_string(),
+            true,
+        );
+
+        let settings = vec![
+            ("api_secret".to_string(), "abc123".to_string()),
+            ("project_token".to_string(), "tok123".to_string()),
+            ("property_naming".to_string(), "mixpanel-js".to_string()),
+        ];
+        let req = Component::page(event.clone(), settings.clone()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        let props = &body[0]["properties"];
+
+        assert_eq!(body[0]["event"], "$mp_web_page_view");
+        assert_eq!(props["mp_lib"], "web");
+        assert_eq!(props["$lib_version"], mixpanel_js::DEFAULT_LIB_VERSION);
+        assert_eq!(props["utm_campaign"], "random");
+        assert!(props.get("utm_creative_format").is_none());
+        assert!(props.get("utm_marketing_tactic").is_none());
+        assert_eq!(props["$referring_domain"], "example.com");
+        assert_eq!(props["current_domain"], "example.com");
+        assert_eq!(props["current_url_path"], "/full-path");
+        assert_eq!(props["current_url_protocol"], "https:");
+        assert_eq!(props["$screen_width"], "1024");
+        assert_eq!(props["$device_id"], "edgee-789");
+        assert!(props.get("user_agent").is_none());
+        assert!(props.get("mp_keyword").is_none());
+        assert!(props.get("path").is_none());
+
+        let mut settings = settings;
+        settings.push(("lib_version".to_string(), "2.60.0".to_string()));
+        let req = Component::page(event, settings).unwrap();
+        assert!(req.body.contains("\"$lib_version\":\"2.60.0\""));
+    }
+
+    #[test]
+    fn click_ids_are_tracked_and_set_once_on_profiles() {
+        let mut event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            false,
+        );
+        event.context.page.url = "https://example.com/landing?gclid=Cj0KCQ".to_string();
+
+        let mut settings = vec![
+            ("api_secret".to_string(), "abc123".to_string()),
+            ("project_token".to_string(), "tok123".to_string()),
+        ];
+        let req = Component::page(event.clone(), settings.clone()).unwrap();
+        assert!(req.body.contains("\"gclid\":\"Cj0KCQ\""));
+
+        let req = Component::user(event.clone(), settings.clone()).unwrap();
+        assert!(!req.body.contains("$set_once"));
+
+        settings.push(("click_ids_on_profile".to_string(), "true".to_string()));
+        let req = Component::user(event, settings).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body[0]["$set_once"]["initial_gclid"], "Cj0KCQ");
+    }
+
+    #[test]
+    fn sensitive_query_params_are_stripped() {
+        let mut event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            false,
+        );
+        event.context.page.url =
+            "https://example.com/reset?reset_token=s3cr3t&lang=fr#form".to_string();
+        event.context.page.referrer =
+            "https://mail.example.com/?email=jane%40example.com".to_string();
+
+        let settings = vec![
+            ("api_secret".to_string(), "abc123".to_string()),
+            ("project_token".to_string(), "tok123".to_string()),
+        ];
+        let req = Component::page(event, settings).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        let props = &body[0]["properties"];
+
+        assert!(!req.body.contains("s3cr3t"));
+        assert!(!req.body.contains("jane"));
+        assert_eq!(
+            props["$current_url"],
+            "https://example.com/reset?lang=fr#form"
+        );
+        assert_eq!(props["current_url_host"], "example.com");
+        assert_eq!(props["current_url_path"], "/reset");
+        assert_eq!(props["current_url_query"], "lang=fr");
+        assert_eq!(props["current_url_hash"], "form");
+    }
+
+    #[test]
+    fn super_properties_are_merged() {
+        let mut event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            false,
+        );
+        if let Data::Page(ref mut data) = event.data {
+            data.properties = vec![("site".to_string(), "landing".to_string())];
+        }
+
+        let mut settings = vec![
+            ("api_secret".to_string(), "abc123".to_string()),
+            ("project_token".to_string(), "tok123".to_string()),
+            (
+                "super_properties".to_string(),
+                "environment=staging,site=fr-shop".to_string(),
+            ),
+        ];
+        let req = Component::page(event.clone(), settings.clone()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body[0]["properties"]["environment"], "staging");
+        assert_eq!(body[0]["properties"]["site"], "landing");
+
+        let req = Component::user(event.clone(), settings.clone()).unwrap();
+        assert!(!req.body.contains("environment"));
+
+        settings.push((
+            "super_properties_precedence".to_string(),
+            "super".to_string(),
+        ));
+        settings.push((
+            "super_properties_on_profile".to_string(),
+            "true".to_string(),
+        ));
+        let req = Component::page(event.clone(), settings.clone()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body[0]["properties"]["site"], "fr-shop");
+
+        let req = Component::user(event, settings).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body[0]["$set"]["environment"], "staging");
+    }
+
+    #[test]
+    fn property_precedence_between_event_and_context() {
+        let mut event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-456".to_string(),
+            "en-GB".to_string(),
+            false,
+        );
+        event.data = Data::Track(
+            crate::exports::edgee::components::data_collection::TrackData {
+                name: "Signup".to_string(),
+                properties: vec![("title".to_string(), "Signup form".to_string())],
+                products: vec![],
+            },
+        );
+
+        let settings = |precedence: &str| {
+            vec![
+                ("api_secret".to_string(), "abc123".to_string()),
+                ("project_token".to_string(), "tok123".to_string()),
+                ("property_precedence".to_string(), precedence.to_string()),
+                ("report_property_collisions".to_string(), "true".to_string()),
+            ]
+        };
+        let properties = |precedence: &str| {
+            let req = Component::track(event.clone(), settings(precedence)).unwrap();
+            let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+            body[0]["properties"].clone()
+        };
+
+        let props = properties("context");
+        assert_eq!(props["title"], "page title");
+        assert_eq!(props["property_collisions"], "[\"title\"]");
+
+        let props = properties("event");
+        assert_eq!(props["title"], "Signup form");
+        assert!(props.get("edgee.title").is_none());
+
+        let mut unreported = settings("context");
+        unreported.pop();
+        let req = Component::track(event.clone(), unreported).unwrap();
+        assert!(!req.body.contains("property_collisions"));
+
+        let props = properties("namespace");
+        assert_eq!(props["title"], "Signup form");
+        assert_eq!(props["edgee.title"], "page title");
+    }
+
+    #[test]
+    fn context_properties_can_be_namespaced() {
+        let event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        let settings = |namespace: &str| {
+            vec![
+                ("api_secret".to_string(), "abc123".to_string()),
+                ("project_token".to_string(), "tok123".to_string()),
+                ("context_namespace".to_string(), namespace.to_string()),
+            ]
+        };
+
+        let req = Component::page(event.clone(), settings("prefix")).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        let props = &body[0]["properties"];
+        assert_eq!(props["edgee.client.locale"], "fr-FR");
+        assert_eq!(props["edgee.campaign.utm_campaign"], "random");
+        assert_eq!(props["edgee.session.session_count"], "2");
+        assert_eq!(props["edgee.page.title"], "page title");
+        assert_eq!(props["$timezone"], "Etc/GMT-1");
+        assert!(props.get("locale").is_none());
+
+        let req = Component::user(event, settings("nested")).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        let set = &body[0]["$set"];
+        assert_eq!(set["edgee"]["client"]["locale"], "fr-FR");
+        assert_eq!(set["edgee"]["campaign"]["channel"], "Unassigned");
+        assert_eq!(set["$referring_domain"], "example.com");
+    }
+
+    #[test]
+    fn context_groups_can_be_disabled_per_event_type() {
+        let event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        let settings = vec![
+            ("api_secret".to_string(), "abc123".to_string()),
+            ("project_token".to_string(), "tok123".to_string()),
+            (
+                "page_enrichment".to_string(),
+                "page,campaign,session".to_string(),
+            ),
+            ("profile_enrichment".to_string(), "client".to_string()),
+        ];
+
+        let req = Component::page(event.clone(), settings.clone()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        let props = &body[0]["properties"];
+        assert_eq!(props["title"], "page title");
+        assert!(props.get("$browser").is_none());
+        assert!(props.get("$city").is_none());
+
+        let req = Component::user(event, settings).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        let set = &body[0]["$set"];
+        assert_eq!(set["$timezone"], "Etc/GMT-1");
+        assert!(set.get("title").is_none());
+        assert!(set.get("session_count").is_none());
+    }
+
+    #[test]
+    fn time_is_sent_in_milliseconds() {
+        let mut event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        event.timestamp = 1_721_044_700;
+        event.timestamp_millis = 1_721_044_700_123;
+        event.timestamp_micros = 1_721_044_700_123_456;
+
+        let mut settings = vec![
+            ("api_secret".to_string(), "abc123".to_string()),
+            ("project_token".to_string(), "tok123".to_string()),
+        ];
+        let req = Component::page(event.clone(), settings.clone()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body[0]["properties"]["time"], 1_721_044_700_123i64);
+        assert!(body[0]["properties"].get("time_corrected").is_none());
+
+        event.timestamp_millis = 0;
+        settings.push(("time_precision".to_string(), "seconds".to_string()));
+        let req = Component::page(event, settings).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body[0]["properties"]["time"], 1_721_044_700i64);
+        assert_eq!(body[0]["properties"]["time_corrected"], true);
+        assert_eq!(body[0]["properties"]["original_timestamp"], 0);
+    }
+
+    #[test]
+    fn insert_id_is_mixpanel_compliant() {
+        let mut event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        let settings = vec![
+            ("api_secret".to_string(), "abc123".to_string()),
+            ("project_token".to_string(), "tok123".to_string()),
+        ];
+        let insert_id = |event: &Event| {
+            let req = Component::page(event.clone(), settings.clone()).unwrap();
+            let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+            body[0]["properties"]["$insert_id"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(insert_id(&event), event.uuid);
+
+        let mut with_own_id = event.clone();
+        if let Data::Page(ref mut data) = with_own_id.data {
+            data.properties
+                .push(("$insert_id".to_string(), "order_1234/retry".to_string()));
+        }
+        assert_eq!(insert_id(&with_own_id), "order1234retry");
+
+        event.uuid = String::new();
+        let fallback = insert_id(&event);
+        assert_eq!(fallback.len(), 32);
+        assert_eq!(fallback, insert_id(&event));
+    }
+
+    #[test]
+    fn payload_limits_are_enforced() {
+        let mut event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        event.data = Data::Track(
+            crate::exports::edgee::components::data_collection::TrackData {
+                name: "x".repeat(300),
+                properties: vec![],
+                products: vec![],
+            },
+        );
+        let mut settings = vec![
+            ("api_secret".to_string(), "abc123".to_string()),
+            ("project_token".to_string(), "tok123".to_string()),
+        ];
+
+        let req = Component::track(event.clone(), settings.clone()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body[0]["event"].as_str().unwrap().len(), 255);
+
+        settings.push(("payload_limit_policy".to_string(), "drop".to_string()));
+        let result = Component::track(event, settings);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn track_events_are_validated_against_the_tracking_plan() {
+        let mut event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        event.data = Data::Track(
+            crate::exports::edgee::components::data_collection::TrackData {
+                name: "Signup".to_string(),
+                properties: vec![
+                    ("plan".to_string(), "gold".to_string()),
+                    ("coupon".to_string(), "WELCOME".to_string()),
+                ],
+                products: vec![],
+            },
+        );
+        let settings = |mode: &str| {
+            vec![
+                ("api_secret".to_string(), "abc123".to_string()),
+                ("project_token".to_string(), "tok123".to_string()),
+                (
+                    "tracking_plan".to_string(),
+                    r#"{"events": {"Signup": {"properties": {"plan": {"enum": ["free", "pro"]}}, "additional_properties": false}}}"#
+                        .to_string(),
+                ),
+                ("tracking_plan_mode".to_string(), mode.to_string()),
+            ]
+        };
+
+        let req = Component::track(event.clone(), settings("warn")).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        let violations: Vec<String> = serde_json::from_str(
+            body[0]["properties"]["$schema_violations"]
+                .as_str()
+                .unwrap(),
+        )
+        .unwrap();
+        // plan and coupon, plus the prop1, prop2 and currency page properties.
+        assert_eq!(violations.len(), 5);
+        assert_eq!(body[0]["properties"]["coupon"], "WELCOME");
+
+        let req = Component::track(event.clone(), settings("strip")).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert!(body[0]["properties"].get("coupon").is_none());
+        assert_eq!(body[0]["properties"]["plan"], "gold");
+
+        let result = Component::track(event, settings("block"));
+        assert!(result.unwrap_err().contains("should be one of free, pro"));
+    }
+
+    #[test]
+    fn page_context_properties_are_validated_against_the_tracking_plan() {
+        let mut event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        event
+            .context
+            .page
+            .properties
+            .push(("debug_token".to_string(), "xyz".to_string()));
+        let settings = |mode: &str| {
+            vec![
+                ("api_secret".to_string(), "abc123".to_string()),
+                ("project_token".to_string(), "tok123".to_string()),
+                (
+                    "tracking_plan".to_string(),
+                    r#"{"events": {"Page View": {"properties": {"prop1": {}, "prop2": {}, "currency": {}}, "additional_properties": false}}}"#
+                        .to_string(),
+                ),
+                ("tracking_plan_mode".to_string(), mode.to_string()),
+            ]
+        };
+
+        let req = Component::page(event.clone(), settings("strip")).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        let props = &body[0]["properties"];
+        assert!(props.get("debug_token").is_none());
+        assert_eq!(props["currency"], "USD");
+        assert!(props.get("$schema_violations").is_none());
+
+        let result = Component::page(event, settings("block"));
+        assert!(result
+            .unwrap_err()
+            .contains("unexpected property 'debug_token'"));
+    }
+
+    #[test]
+    fn track_ingestion_mode_uses_the_project_token_only() {
+        let event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        let settings = vec![
+            ("project_token".to_string(), "tok123".to_string()),
+            ("ingestion_mode".to_string(), "track".to_string()),
+        ];
+
+        let req = Component::page(event.clone(), settings).unwrap();
+        assert_eq!(req.url, "https://api.mixpanel.com/track");
+        assert!(req.headers.iter().all(|(name, _)| name != "Authorization"));
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body[0]["properties"]["token"], "tok123");
+        assert!(body[0]["properties"].get("$import").is_none());
+
+        let missing_secret = vec![("project_token".to_string(), "tok123".to_string())];
+        let result = Component::page(event, missing_secret);
+        assert!(result.unwrap_err().contains("api_secret"));
+    }
+
+    #[test]
+    fn import_can_use_a_service_account() {
+        let event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        let mut settings = vec![
+            ("project_token".to_string(), "tok123".to_string()),
+            (
+                "service_account_username".to_string(),
+                "sa.user".to_string(),
+            ),
+            (
+                "service_account_secret".to_string(),
+                "sa-secret".to_string(),
+            ),
+        ];
+
+        let result = Component::page(event.clone(), settings.clone());
+        assert!(result.unwrap_err().contains("'project_id'"));
+
+        settings.push(("project_id".to_string(), "42".to_string()));
+        let req = Component::page(event, settings).unwrap();
+        assert!(req.url.contains("/import?strict=1&project_id=42"));
+        assert!(req.headers.contains(&(
+            "Authorization".to_string(),
+            "Basic c2EudXNlcjpzYS1zZWNyZXQ=".to_string()
+        )));
+        let body: serde_json::Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body[0]["properties"]["token"], "tok123");
+    }
+
+    #[test]
+    fn api_host_is_used_for_every_endpoint() {
+        let event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        let settings = |mode: &str| {
+            vec![
+                ("api_secret".to_string(), "abc123".to_string()),
+                ("project_token".to_string(), "tok123".to_string()),
+                ("region".to_string(), "api-eu".to_string()),
+                (
+                    "api_host".to_string(),
+                    "https://proxy.example.com/mixpanel/".to_string(),
+                ),
+                ("ingestion_mode".to_string(), mode.to_string()),
+            ]
+        };
+
+        let req = Component::page(event.clone(), settings("import")).unwrap();
+        assert_eq!(
+            req.url,
+            "https://proxy.example.com/mixpanel/import?strict=1"
+        );
+        let req = Component::page(event.clone(), settings("track")).unwrap();
+        assert_eq!(req.url, "https://proxy.example.com/mixpanel/track");
+        let req = Component::user(event, settings("import")).unwrap();
+        assert_eq!(req.url, "https://proxy.example.com/mixpanel/engage");
+    }
+
+    #[test]
+    fn strict_and_verbose_query_parameters() {
+        let event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        let settings = |mode: &str, extra: &[(&str, &str)]| {
+            let mut settings = vec![
+                ("api_secret".to_string(), "abc123".to_string()),
+                ("project_token".to_string(), "tok123".to_string()),
+                ("project_id".to_string(), "42&strict=0".to_string()),
+                ("ingestion_mode".to_string(), mode.to_string()),
+            ];
+            settings.extend(extra.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+            settings
+        };
+
+        let req = Component::page(event.clone(), settings("import", &[])).unwrap();
+        assert_eq!(
+            req.url,
+            "https://api.mixpanel.com/import?strict=1&project_id=42%26strict%3D0"
+        );
+        let req =
+            Component::page(event.clone(), settings("import", &[("strict", "false")])).unwrap();
+        assert_eq!(
+            req.url,
+            "https://api.mixpanel.com/import?project_id=42%26strict%3D0"
+        );
+
+        let verbose = [("verbose", "true")];
+        let req = Component::page(event.clone(), settings("track", &verbose)).unwrap();
+        assert_eq!(req.url, "https://api.mixpanel.com/track?verbose=1");
+        let req = Component::user(event.clone(), settings("import", &verbose)).unwrap();
+        assert_eq!(req.url, "https://api.mixpanel.com/engage?verbose=1");
+        let req = Component::user(event, settings("import", &[])).unwrap();
+        assert_eq!(req.url, "https://api.mixpanel.com/engage");
+    }
+
+    #[test]
+    fn region_is_validated() {
+        let event = sample_page_event(
+            Some(Consent::Granted),
+            "edgee-123".to_string(),
+            "fr-FR".to_string(),
+            true,
+        );
+        let settings = |region: &str| {
+            vec![
+                ("api_secret".to_string(), "abc123".to_string()),
+                ("project_token".to_string(), "tok123".to_string()),
+                ("region".to_string(), region.to_string()),
+            ]
+        };
+
+        let req = Component::page(event.clone(), settings("eu")).unwrap();
+        assert!(req.url.starts_with("https://api-eu.mixpanel.com/import"));
+        assert!(!req.body.contains("$mp_api_endpoint"));
+
+        let req = Component::user(event.clone(), settings("IN")).unwrap();
+        assert_eq!(req.url, "https://api-in.mixpanel.com/engage");
+        assert!(!req.body.contains("$mp_api_endpoint"));
+
+        let result = Component::page(event, settings("europe"));
+        assert!(result.unwrap_err().contains("Invalid 'region' setting"));
     }
 }
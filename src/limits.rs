@@ -0,0 +1,337 @@
+use crate::exports::edgee::components::data_collection::EdgeeRequest;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Maximum length, in characters, of event names, distinct_ids and property names.
+pub const MAX_NAME_LEN: usize = 255;
+/// Maximum number of properties of an event or profile update.
+pub const MAX_PROPERTIES: usize = 255;
+/// Maximum size, in bytes, of a string property value.
+pub const MAX_VALUE_BYTES: usize = 8 * 1024;
+/// Maximum size, in bytes, of a request body.
+pub const MAX_BODY_BYTES: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitPolicy {
+    /// Over-long names and values are truncated.
+    Truncate,
+    /// Over-long values are dropped, and so are events whose name or distinct_id is too long.
+    Drop,
+}
+
+/// Order in which properties are kept when an event has too many of them
+/// or its body is too large.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    Reserved,
+    Event,
+    Context,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadLimits {
+    pub policy: LimitPolicy,
+}
+
+impl PayloadLimits {
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let policy = match settings_map
+            .get("payload_limit_policy")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("truncate") => LimitPolicy::Truncate,
+            Some("drop") => LimitPolicy::Drop,
+            Some(other) => {
+                return Err(anyhow::anyhow!(
+                "Invalid 'payload_limit_policy' setting: '{other}' (expected 'truncate' or 'drop')"
+            ))
+            }
+        };
+
+        Ok(Self { policy })
+    }
+
+    /// Brings an `/import` or `/engage` request within Mixpanel's limits.
+    /// `event_keys` are the properties sent by the event itself, kept in
+    /// preference to context properties.
+    pub fn enforce(
+        &self,
+        mut request: EdgeeRequest,
+        event_keys: &HashSet<String>,
+    ) -> Result<EdgeeRequest, String> {
+        let mut payload: Value = serde_json::from_str(&request.body)
+            .map_err(|e| format!("Invalid request body: {e}"))?;
+
+        for item in payload.as_array_mut().into_iter().flatten() {
+            self.enforce_identifiers(item)?;
+            for properties in property_maps(item) {
+                self.enforce_properties(properties, event_keys);
+            }
+        }
+
+        let mut body = payload.to_string();
+        if body.len() > MAX_BODY_BYTES {
+            for item in payload.as_array_mut().into_iter().flatten() {
+                for properties in property_maps(item) {
+                    shrink(properties, event_keys, body.len() - MAX_BODY_BYTES);
+                }
+            }
+            body = payload.to_string();
+        }
+        if body.len() > MAX_BODY_BYTES {
+            return Err(format!(
+                "Event dropped: body exceeds {MAX_BODY_BYTES} bytes"
+            ));
+        }
+
+        request.body = body;
+        Ok(request)
+    }
+
+    fn enforce_identifiers(&self, item: &mut Value) -> Result<(), String> {
+        if let Some(name) = item.get_mut("event") {
+            self.enforce_identifier(name, "event name")?;
+        }
+        if let Some(distinct_id) = item.get_mut("$distinct_id") {
+            self.enforce_identifier(distinct_id, "distinct_id")?;
+        }
+        if let Some(properties) = item.get_mut("properties").and_then(Value::as_object_mut) {
+            for key in ["distinct_id", "$distinct_id", "$user_id"] {
+                if let Some(distinct_id) = properties.get_mut(key) {
+                    self.enforce_identifier(distinct_id, "distinct_id")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn enforce_identifier(&self, value: &mut Value, what: &str) -> Result<(), String> {
+        let Some(text) = value.as_str() else {
+            return Ok(());
+        };
+        if text.chars().count() <= MAX_NAME_LEN {
+            return Ok(());
+        }
+
+        match self.policy {
+            LimitPolicy::Truncate => {
+                *value = truncate_chars(text, MAX_NAME_LEN).into();
+                Ok(())
+            }
+            LimitPolicy::Drop => Err(format!(
+                "Event dropped: {what} exceeds {MAX_NAME_LEN} characters"
+            )),
+        }
+    }
+
+    fn enforce_properties(
+        &self,
+        properties: &mut Map<String, Value>,
+        event_keys: &HashSet<String>,
+    ) {
+        let entries = std::mem::take(properties);
+        for (key, mut value) in entries {
+            let key = if key.chars().count() > MAX_NAME_LEN {
+                match self.policy {
+                    LimitPolicy::Truncate => truncate_chars(&key, MAX_NAME_LEN),
+                    LimitPolicy::Drop => continue,
+                }
+            } else {
+                key
+            };
+            if self.enforce_value(&mut value) {
+                properties.entry(key).or_insert(value);
+            }
+        }
+
+        if properties.len() > MAX_PROPERTIES {
+            let mut keys: Vec<String> = properties.keys().cloned().collect();
+            keys.sort_by_key(|key| priority(key, event_keys));
+            for key in &keys[MAX_PROPERTIES..] {
+                properties.remove(key);
+            }
+        }
+    }
+
+    /// Returns whether the value is kept.
+    fn enforce_value(&self, value: &mut Value) -> bool {
+        match value {
+            Value::String(text) if text.len() > MAX_VALUE_BYTES => match self.policy {
+                LimitPolicy::Truncate => {
+                    *text = truncate_bytes(text, MAX_VALUE_BYTES).to_string();
+                    true
+                }
+                LimitPolicy::Drop => false,
+            },
+            Value::Object(object) => {
+                object.retain(|_, value| self.enforce_value(value));
+                true
+            }
+            _ => true,
+        }
+    }
+}
+
+/// The property maps of an `/import` event or an `/engage` update.
+fn property_maps(item: &mut Value) -> Vec<&mut Map<String, Value>> {
+    let Some(item) = item.as_object_mut() else {
+        return Vec::new();
+    };
+
+    item.iter_mut()
+        .filter(|(key, _)| matches!(key.as_str(), "properties" | "$set" | "$set_once"))
+        .filter_map(|(_, value)| value.as_object_mut())
+        .collect()
+}
+
+/// Removes non-reserved properties, context first and largest first,
+/// until about `excess` bytes are freed.
+fn shrink(properties: &mut Map<String, Value>, event_keys: &HashSet<String>, excess: usize) {
+    let mut candidates: Vec<(Priority, usize, String)> = properties
+        .iter()
+        .map(|(key, value)| {
+            (
+                priority(key, event_keys),
+                key.len() + value.to_string().len() + 4,
+                key.clone(),
+            )
+        })
+        .filter(|(priority, _, _)| *priority != Priority::Reserved)
+        .collect();
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+
+    let mut freed = 0;
+    for (_, size, key) in candidates {
+        if freed >= excess {
+            break;
+        }
+        properties.remove(&key);
+        freed += size;
+    }
+}
+
+fn priority(key: &str, event_keys: &HashSet<String>) -> Priority {
+    if key.starts_with('$')
+        || key.starts_with("mp_")
+        || matches!(key, "token" | "time" | "distinct_id" | "ip")
+    {
+        Priority::Reserved
+    } else if event_keys.contains(key) {
+        Priority::Event
+    } else {
+        Priority::Context
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    text.chars().take(max).collect()
+}
+
+fn truncate_bytes(text: &str, max: usize) -> &str {
+    let mut end = max.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exports::edgee::components::data_collection::HttpMethod;
+
+    fn request(body: Value) -> EdgeeRequest {
+        EdgeeRequest {
+            method: HttpMethod::Post,
+            url: "https://api.mixpanel.com/import".into(),
+            headers: vec![],
+            body: body.to_string(),
+            forward_client_headers: false,
+        }
+    }
+
+    fn limits(policy: LimitPolicy) -> PayloadLimits {
+        PayloadLimits { policy }
+    }
+
+    fn enforce(policy: LimitPolicy, body: Value, event_keys: &[&str]) -> Result<Value, String> {
+        let event_keys = event_keys.iter().map(|k| k.to_string()).collect();
+        limits(policy)
+            .enforce(request(body), &event_keys)
+            .map(|req| serde_json::from_str(&req.body).unwrap())
+    }
+
+    #[test]
+    fn truncates_names_and_values() {
+        let body = serde_json::json!([{
+            "event": "e".repeat(300),
+            "properties": {
+                "distinct_id": "d".repeat(300),
+                "k".repeat(300): "value",
+                "description": "é".repeat(MAX_VALUE_BYTES),
+            }
+        }]);
+        let payload = enforce(LimitPolicy::Truncate, body.clone(), &[]).unwrap();
+        let event = &payload[0];
+        assert_eq!(event["event"].as_str().unwrap().len(), MAX_NAME_LEN);
+        assert_eq!(
+            event["properties"]["distinct_id"].as_str().unwrap().len(),
+            MAX_NAME_LEN
+        );
+        assert_eq!(event["properties"]["k".repeat(MAX_NAME_LEN)], "value");
+        assert_eq!(
+            event["properties"]["description"].as_str().unwrap().len(),
+            MAX_VALUE_BYTES
+        );
+
+        assert!(enforce(LimitPolicy::Drop, body, &[]).is_err());
+    }
+
+    #[test]
+    fn drop_policy_removes_oversized_values() {
+        let body = serde_json::json!([{
+            "$distinct_id": "user-1",
+            "$set": { "bio": "x".repeat(MAX_VALUE_BYTES + 1), "plan": "pro" }
+        }]);
+        let payload = enforce(LimitPolicy::Drop, body, &[]).unwrap();
+        assert!(payload[0]["$set"].get("bio").is_none());
+        assert_eq!(payload[0]["$set"]["plan"], "pro");
+    }
+
+    #[test]
+    fn keeps_reserved_then_event_properties() {
+        let mut properties = Map::new();
+        properties.insert("$browser".into(), "Chrome".into());
+        properties.insert("plan".into(), "pro".into());
+        for i in 0..300 {
+            properties.insert(format!("context_{i:03}"), "x".into());
+        }
+        let body = serde_json::json!([{ "event": "Signup", "properties": properties }]);
+
+        let payload = enforce(LimitPolicy::Truncate, body, &["plan"]).unwrap();
+        let properties = payload[0]["properties"].as_object().unwrap();
+        assert_eq!(properties.len(), MAX_PROPERTIES);
+        assert!(properties.contains_key("$browser"));
+        assert!(properties.contains_key("plan"));
+    }
+
+    #[test]
+    fn shrinks_oversized_bodies() {
+        let mut properties = Map::new();
+        properties.insert("token".into(), "abc".into());
+        properties.insert("plan".into(), "pro".into());
+        for i in 0..200 {
+            properties.insert(
+                format!("context_{i:03}"),
+                "x".repeat(MAX_VALUE_BYTES).into(),
+            );
+        }
+        let body = serde_json::json!([{ "event": "Signup", "properties": properties }]);
+
+        let payload = enforce(LimitPolicy::Truncate, body, &["plan"]).unwrap();
+        assert!(payload.to_string().len() <= MAX_BODY_BYTES);
+        assert_eq!(payload[0]["properties"]["token"], "abc");
+        assert_eq!(payload[0]["properties"]["plan"], "pro");
+    }
+}
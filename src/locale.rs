@@ -0,0 +1,501 @@
+use std::collections::HashMap;
+
+/// Daylight saving time schedules of the zones in [`ZONES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dst {
+    None,
+    /// European Union: last Sunday of March to last Sunday of October, 01:00 UTC.
+    Eu,
+    /// United States and Canada: second Sunday of March to first Sunday of November, 02:00 local.
+    Us,
+    /// South-eastern Australia: first Sunday of October to first Sunday of April, 02:00 standard.
+    Au,
+    /// New Zealand: last Sunday of September to first Sunday of April, 02:00 standard.
+    Nz,
+}
+
+/// IANA zones with their standard UTC offset in minutes.
+const ZONES: &[(&str, i32, Dst)] = &[
+    ("Etc/UTC", 0, Dst::None),
+    ("Europe/London", 0, Dst::Eu),
+    ("Europe/Dublin", 0, Dst::Eu),
+    ("Europe/Lisbon", 0, Dst::Eu),
+    ("Atlantic/Canary", 0, Dst::Eu),
+    ("Atlantic/Reykjavik", 0, Dst::None),
+    ("Europe/Paris", 60, Dst::Eu),
+    ("Europe/Berlin", 60, Dst::Eu),
+    ("Europe/Madrid", 60, Dst::Eu),
+    ("Europe/Rome", 60, Dst::Eu),
+    ("Europe/Amsterdam", 60, Dst::Eu),
+    ("Europe/Brussels", 60, Dst::Eu),
+    ("Europe/Luxembourg", 60, Dst::Eu),
+    ("Europe/Monaco", 60, Dst::Eu),
+    ("Europe/Vienna", 60, Dst::Eu),
+    ("Europe/Zurich", 60, Dst::Eu),
+    ("Europe/Stockholm", 60, Dst::Eu),
+    ("Europe/Oslo", 60, Dst::Eu),
+    ("Europe/Copenhagen", 60, Dst::Eu),
+    ("Europe/Warsaw", 60, Dst::Eu),
+    ("Europe/Prague", 60, Dst::Eu),
+    ("Europe/Budapest", 60, Dst::Eu),
+    ("Europe/Belgrade", 60, Dst::Eu),
+    ("Africa/Lagos", 60, Dst::None),
+    ("Europe/Athens", 120, Dst::Eu),
+    ("Europe/Helsinki", 120, Dst::Eu),
+    ("Europe/Bucharest", 120, Dst::Eu),
+    ("Europe/Sofia", 120, Dst::Eu),
+    ("Europe/Kyiv", 120, Dst::Eu),
+    ("Europe/Kiev", 120, Dst::Eu),
+    ("Europe/Riga", 120, Dst::Eu),
+    ("Europe/Vilnius", 120, Dst::Eu),
+    ("Europe/Tallinn", 120, Dst::Eu),
+    ("Africa/Johannesburg", 120, Dst::None),
+    ("Europe/Istanbul", 180, Dst::None),
+    ("Europe/Moscow", 180, Dst::None),
+    ("Africa/Nairobi", 180, Dst::None),
+    ("Asia/Riyadh", 180, Dst::None),
+    ("Asia/Tehran", 210, Dst::None),
+    ("Asia/Dubai", 240, Dst::None),
+    ("Asia/Karachi", 300, Dst::None),
+    ("Asia/Kolkata", 330, Dst::None),
+    ("Asia/Calcutta", 330, Dst::None),
+    ("Asia/Kathmandu", 345, Dst::None),
+    ("Asia/Dhaka", 360, Dst::None),
+    ("Asia/Bangkok", 420, Dst::None),
+    ("Asia/Jakarta", 420, Dst::None),
+    ("Asia/Ho_Chi_Minh", 420, Dst::None),
+    ("Asia/Shanghai", 480, Dst::None),
+    ("Asia/Hong_Kong", 480, Dst::None),
+    ("Asia/Singapore", 480, Dst::None),
+    ("Asia/Taipei", 480, Dst::None),
+    ("Asia/Manila", 480, Dst::None),
+    ("Australia/Perth", 480, Dst::None),
+    ("Asia/Tokyo", 540, Dst::None),
+    ("Asia/Seoul", 540, Dst::None),
+    ("Australia/Darwin", 570, Dst::None),
+    ("Australia/Adelaide", 570, Dst::Au),
+    ("Australia/Brisbane", 600, Dst::None),
+    ("Australia/Sydney", 600, Dst::Au),
+    ("Australia/Melbourne", 600, Dst::Au),
+    ("Australia/Hobart", 600, Dst::Au),
+    ("Pacific/Auckland", 720, Dst::Nz),
+    ("America/Sao_Paulo", -180, Dst::None),
+    ("America/Argentina/Buenos_Aires", -180, Dst::None),
+    ("America/Halifax", -240, Dst::Us),
+    ("America/Caracas", -240, Dst::None),
+    ("America/New_York", -300, Dst::Us),
+    ("America/Toronto", -300, Dst::Us),
+    ("America/Detroit", -300, Dst::Us),
+    ("America/Bogota", -300, Dst::None),
+    ("America/Lima", -300, Dst::None),
+    ("America/Chicago", -360, Dst::Us),
+    ("America/Winnipeg", -360, Dst::Us),
+    ("America/Mexico_City", -360, Dst::None),
+    ("America/Denver", -420, Dst::Us),
+    ("America/Edmonton", -420, Dst::Us),
+    ("America/Phoenix", -420, Dst::None),
+    ("America/Los_Angeles", -480, Dst::Us),
+    ("America/Vancouver", -480, Dst::Us),
+    ("America/Anchorage", -540, Dst::Us),
+    ("Pacific/Honolulu", -600, Dst::None),
+];
+
+/// Unambiguous time zone abbreviations with their fixed UTC offset in minutes.
+/// An abbreviation already says whether summer time applies (`CET` versus
+/// `CEST`), so it maps to a fixed-offset `Etc/GMT` zone rather than a city.
+/// Ambiguous ones such as `CST` or `IST` are left unresolved.
+const ABBREVIATIONS: &[(&str, i32)] = &[
+    ("UTC", 0),
+    ("GMT", 0),
+    ("Z", 0),
+    ("WET", 0),
+    ("WEST", 60),
+    ("BST", 60),
+    ("CET", 60),
+    ("CEST", 120),
+    ("MET", 60),
+    ("EET", 120),
+    ("EEST", 180),
+    ("MSK", 180),
+    ("JST", 540),
+    ("KST", 540),
+    ("HKT", 480),
+    ("SGT", 480),
+    ("AEST", 600),
+    ("AEDT", 660),
+    ("NZST", 720),
+    ("NZDT", 780),
+    ("EST", -300),
+    ("EDT", -240),
+    ("CDT", -300),
+    ("MST", -420),
+    ("MDT", -360),
+    ("PST", -480),
+    ("PDT", -420),
+    ("AKST", -540),
+    ("HST", -600),
+    ("BRT", -180),
+];
+
+const IANA_AREAS: &[&str] = &[
+    "Africa",
+    "America",
+    "Antarctica",
+    "Arctic",
+    "Asia",
+    "Atlantic",
+    "Australia",
+    "Europe",
+    "Indian",
+    "Pacific",
+    "Etc",
+];
+
+/// Adds `language` and `language_region` parsed from a BCP-47 locale
+/// (or the first entry of an `Accept-Language` list), e.g. `fr_fr` gives `fr` and `fr-FR`.
+pub fn enrich_locale(props: &mut HashMap<String, String>, locale: &str) {
+    let tag = locale
+        .split([',', ';'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .replace('_', "-");
+    let mut subtags = tag.split('-').filter(|s| !s.is_empty());
+
+    let Some(language) = subtags
+        .next()
+        .filter(|l| (2..=3).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(|l| l.to_lowercase())
+    else {
+        return;
+    };
+
+    let mut canonical = language.clone();
+    let mut has_region = false;
+    for subtag in subtags {
+        match subtag.len() {
+            4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                canonical.push('-');
+                canonical.push_str(&subtag[..1].to_uppercase());
+                canonical.push_str(&subtag[1..].to_lowercase());
+            }
+            2 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                canonical.push('-');
+                canonical.push_str(&subtag.to_uppercase());
+                has_region = true;
+                break;
+            }
+            3 if subtag.chars().all(|c| c.is_ascii_digit()) => {
+                canonical.push('-');
+                canonical.push_str(subtag);
+                has_region = true;
+                break;
+            }
+            _ => break,
+        }
+    }
+
+    props.insert("language".into(), language);
+    if has_region {
+        props.insert("language_region".into(), canonical);
+    }
+}
+
+/// Adds the IANA `$timezone` and its `timezone_utc_offset` at `timestamp`
+/// (Unix seconds). Abbreviations such as `CET` are mapped to an `Etc/GMT` zone;
+/// whenever the value sent to Mixpanel differs from the client's, or the
+/// client's value cannot be resolved, the original is kept in `timezone_raw`.
+pub fn enrich_timezone(props: &mut HashMap<String, String>, timezone: &str, timestamp: i64) {
+    let raw = timezone.trim();
+    if raw.is_empty() {
+        return;
+    }
+
+    let iana = ABBREVIATIONS
+        .iter()
+        .find(|(abbreviation, _)| abbreviation.eq_ignore_ascii_case(raw))
+        .map(|(_, offset)| fixed_offset_zone(*offset))
+        .or_else(|| is_iana_name(raw).then(|| raw.to_string()));
+
+    match iana {
+        Some(zone) => {
+            if let Some(offset) = utc_offset_minutes(&zone, timestamp) {
+                props.insert("timezone_utc_offset".into(), format_offset(offset));
+            }
+            if zone != raw {
+                props.insert("timezone_raw".into(), raw.to_string());
+            }
+            props.insert("$timezone".into(), zone);
+        }
+        None => {
+            props.insert("timezone_raw".into(), raw.to_string());
+        }
+    }
+}
+
+fn is_iana_name(name: &str) -> bool {
+    let Some((area, location)) = name.split_once('/') else {
+        return false;
+    };
+
+    IANA_AREAS.contains(&area)
+        && !location.is_empty()
+        && location.split('/').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_uppercase())
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
+        })
+}
+
+/// `Etc/GMT` zone of a whole-hour UTC offset. Its sign is inverted, POSIX style:
+/// UTC-05:00 is `Etc/GMT+5`.
+fn fixed_offset_zone(minutes: i32) -> String {
+    match minutes / 60 {
+        0 => "Etc/UTC".to_string(),
+        hours => format!("Etc/GMT{:+}", -hours),
+    }
+}
+
+/// UTC offset of `zone` at `timestamp`, for the zones of [`ZONES`] and `Etc/GMT±h`.
+fn utc_offset_minutes(zone: &str, timestamp: i64) -> Option<i32> {
+    if let Some(hours) = zone.strip_prefix("Etc/GMT").filter(|h| !h.is_empty()) {
+        return hours
+            .parse::<i32>()
+            .ok()
+            .filter(|h| (-14..=12).contains(h))
+            .map(|h| -h * 60);
+    }
+    let (_, standard, dst) = ZONES.iter().find(|(name, _, _)| *name == zone)?;
+    let standard_seconds = i64::from(*standard) * 60;
+    let year = civil_from_days(timestamp.div_euclid(86_400)).0;
+    let at =
+        |month: u32, day: i64, hour: i64| days_from_civil(year, month, day) * 86_400 + hour * 3_600;
+
+    let in_dst = match dst {
+        Dst::None => false,
+        Dst::Eu => {
+            let start = at(3, last_sunday(year, 3), 1);
+            let end = at(10, last_sunday(year, 10), 1);
+            (start..end).contains(&timestamp)
+        }
+        Dst::Us => {
+            let start = at(3, nth_sunday(year, 3, 2), 2) - standard_seconds;
+            let end = at(11, nth_sunday(year, 11, 1), 1) - standard_seconds;
+            (start..end).contains(&timestamp)
+        }
+        Dst::Au => {
+            let end = at(4, nth_sunday(year, 4, 1), 2) - standard_seconds;
+            let start = at(10, nth_sunday(year, 10, 1), 2) - standard_seconds;
+            timestamp < end || timestamp >= start
+        }
+        Dst::Nz => {
+            let end = at(4, nth_sunday(year, 4, 1), 2) - standard_seconds;
+            let start = at(9, last_sunday(year, 9), 2) - standard_seconds;
+            timestamp < end || timestamp >= start
+        }
+    };
+
+    Some(if in_dst { standard + 60 } else { *standard })
+}
+
+fn format_offset(minutes: i32) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = minutes.abs();
+    format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Day of the month of the `n`th Sunday of `month`.
+fn nth_sunday(year: i64, month: u32, n: i64) -> i64 {
+    let first = days_from_civil(year, month, 1);
+    let first_sunday = 1 + (7 - weekday(first)) % 7;
+    first_sunday + (n - 1) * 7
+}
+
+fn last_sunday(year: i64, month: u32) -> i64 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    let last = days_from_civil(next_year, next_month, 1) - 1;
+    let day_of_month = last - days_from_civil(year, month, 1) + 1;
+    day_of_month - weekday(last)
+}
+
+/// 0 for Sunday through 6 for Saturday.
+fn weekday(days: i64) -> i64 {
+    (days + 4).rem_euclid(7)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: u32, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-15T12:00:00Z and 2024-07-15T12:00:00Z
+    const WINTER: i64 = 1_705_320_000;
+    const SUMMER: i64 = 1_721_044_800;
+
+    fn locale(value: &str) -> HashMap<String, String> {
+        let mut props = HashMap::new();
+        enrich_locale(&mut props, value);
+        props
+    }
+
+    fn timezone(value: &str, timestamp: i64) -> HashMap<String, String> {
+        let mut props = HashMap::new();
+        enrich_timezone(&mut props, value, timestamp);
+        props
+    }
+
+    #[test]
+    fn parses_locales() {
+        let props = locale("fr_fr");
+        assert_eq!(props.get("language"), Some(&"fr".to_string()));
+        assert_eq!(props.get("language_region"), Some(&"fr-FR".to_string()));
+
+        let props = locale("zh-hant-tw,zh;q=0.9");
+        assert_eq!(props.get("language"), Some(&"zh".to_string()));
+        assert_eq!(
+            props.get("language_region"),
+            Some(&"zh-Hant-TW".to_string())
+        );
+
+        let props = locale("es-419");
+        assert_eq!(props.get("language_region"), Some(&"es-419".to_string()));
+
+        let props = locale("en");
+        assert_eq!(props.get("language"), Some(&"en".to_string()));
+        assert!(!props.contains_key("language_region"));
+
+        assert!(locale("").is_empty());
+        assert!(locale("*").is_empty());
+    }
+
+    #[test]
+    fn normalises_abbreviations() {
+        let props = timezone("CET", WINTER);
+        assert_eq!(props.get("$timezone"), Some(&"Etc/GMT-1".to_string()));
+        assert_eq!(
+            props.get("timezone_utc_offset"),
+            Some(&"+01:00".to_string())
+        );
+        assert_eq!(props.get("timezone_raw"), Some(&"CET".to_string()));
+
+        let props = timezone("GMT", SUMMER);
+        assert_eq!(props.get("$timezone"), Some(&"Etc/UTC".to_string()));
+        assert_eq!(
+            props.get("timezone_utc_offset"),
+            Some(&"+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn abbreviations_have_fixed_offsets() {
+        let offset = |value: &str, timestamp: i64| {
+            timezone(value, timestamp)
+                .get("timezone_utc_offset")
+                .cloned()
+        };
+
+        // Standard time abbreviations in summer.
+        assert_eq!(offset("CET", SUMMER).as_deref(), Some("+01:00"));
+        assert_eq!(offset("EST", SUMMER).as_deref(), Some("-05:00"));
+        assert_eq!(offset("MST", SUMMER).as_deref(), Some("-07:00"));
+        assert_eq!(offset("AEST", WINTER).as_deref(), Some("+10:00"));
+        // Summer time abbreviations in winter.
+        assert_eq!(offset("CEST", WINTER).as_deref(), Some("+02:00"));
+        assert_eq!(offset("EDT", WINTER).as_deref(), Some("-04:00"));
+        assert_eq!(offset("AEDT", SUMMER).as_deref(), Some("+11:00"));
+
+        assert_eq!(
+            timezone("EST", SUMMER).get("$timezone"),
+            Some(&"Etc/GMT+5".to_string())
+        );
+        assert_eq!(offset("Etc/GMT-9", SUMMER).as_deref(), Some("+09:00"));
+    }
+
+    #[test]
+    fn derives_offsets_for_iana_zones() {
+        let props = timezone("America/New_York", SUMMER);
+        assert_eq!(
+            props.get("$timezone"),
+            Some(&"America/New_York".to_string())
+        );
+        assert_eq!(
+            props.get("timezone_utc_offset"),
+            Some(&"-04:00".to_string())
+        );
+        assert!(!props.contains_key("timezone_raw"));
+
+        let props = timezone("Australia/Sydney", WINTER);
+        assert_eq!(
+            props.get("timezone_utc_offset"),
+            Some(&"+11:00".to_string())
+        );
+
+        let props = timezone("Asia/Kolkata", SUMMER);
+        assert_eq!(
+            props.get("timezone_utc_offset"),
+            Some(&"+05:30".to_string())
+        );
+
+        let props = timezone("America/Argentina/Cordoba", SUMMER);
+        assert_eq!(
+            props.get("$timezone"),
+            Some(&"America/Argentina/Cordoba".to_string())
+        );
+        assert!(!props.contains_key("timezone_utc_offset"));
+    }
+
+    #[test]
+    fn flags_unresolvable_timezones() {
+        let props = timezone("IST", SUMMER);
+        assert!(!props.contains_key("$timezone"));
+        assert_eq!(props.get("timezone_raw"), Some(&"IST".to_string()));
+
+        let props = timezone("Mars/Olympus", SUMMER);
+        assert!(!props.contains_key("$timezone"));
+    }
+
+    #[test]
+    fn dst_transitions() {
+        // 2024-03-31T00:59:59Z and 01:00:00Z, around the EU switch.
+        assert_eq!(utc_offset_minutes("Europe/Paris", 1_711_846_799), Some(60));
+        assert_eq!(utc_offset_minutes("Europe/Paris", 1_711_846_800), Some(120));
+        // 2024-03-10T06:59:59Z and 07:00:00Z, 02:00 EST on the US switch day.
+        assert_eq!(
+            utc_offset_minutes("America/New_York", 1_710_053_999),
+            Some(-300)
+        );
+        assert_eq!(
+            utc_offset_minutes("America/New_York", 1_710_054_000),
+            Some(-240)
+        );
+    }
+}
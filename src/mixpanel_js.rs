@@ -0,0 +1,153 @@
+use crate::click_ids;
+use crate::exports::edgee::components::data_collection::{Client, Data, Event};
+use crate::helpers::{insert_if_nonempty, url_host};
+use crate::referrer::Referrer;
+use crate::user_agent::{self, UserAgent};
+use crate::utm::{UtmNaming, UtmParams};
+use std::collections::HashMap;
+
+/// Event name mixpanel-js uses for `track_pageview`.
+pub const PAGE_VIEW_EVENT: &str = "$mp_web_page_view";
+
+/// `$lib_version` sent when the `lib_version` setting is not set.
+pub const DEFAULT_LIB_VERSION: &str = "2.55.1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyNaming {
+    /// Edgee context fields, forwarded under the component's historical names.
+    Edgee,
+    /// Exactly the default properties the mixpanel-js browser SDK would send.
+    MixpanelJs,
+}
+
+impl PropertyNaming {
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        match settings_map
+            .get("property_naming")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("edgee") => Ok(PropertyNaming::Edgee),
+            Some("mixpanel-js") => Ok(PropertyNaming::MixpanelJs),
+            Some(other) => Err(anyhow::anyhow!(
+                "Invalid 'property_naming' setting: '{other}' (expected 'edgee' or 'mixpanel-js')"
+            )),
+        }
+    }
+
+    pub fn page_view_event(&self) -> &'static str {
+        match self {
+            PropertyNaming::Edgee => "Page View",
+            PropertyNaming::MixpanelJs => PAGE_VIEW_EVENT,
+        }
+    }
+}
+
+/// Adds the properties mixpanel-js attaches to every event, plus the
+/// `current_*` properties of `$mp_web_page_view` for page events.
+/// `lib_version` is the mixpanel-js version reported in `$lib_version`.
+pub fn enrich_event(props: &mut HashMap<String, String>, event: &Event, lib_version: &str) {
+    let context = &event.context;
+    let page = &context.page;
+    let client = &context.client;
+
+    enrich_device(props, client);
+
+    insert_if_nonempty(props, "$current_url", &page.url);
+    insert_if_nonempty(props, "$referrer", &page.referrer);
+    let referrer = Referrer::parse(&page.referrer);
+    if let Some(domain) = &referrer.domain {
+        props.insert("$referring_domain".into(), domain.clone());
+    }
+    if let Some(engine) = referrer.search_engine {
+        props.insert("$search_engine".into(), engine.into());
+    }
+    if let Some(keyword) = &referrer.search_keyword {
+        props.insert("mp_keyword".into(), keyword.clone());
+    }
+
+    // mixpanel-js persists the referrer of the very first page view as a super property.
+    if context.session.session_count <= 1 && context.session.session_start {
+        let (initial_referrer, initial_domain) = match &referrer.domain {
+            Some(domain) => (page.referrer.clone(), domain.clone()),
+            None => ("$direct".to_string(), "$direct".to_string()),
+        };
+        props.insert("$initial_referrer".into(), initial_referrer);
+        props.insert("$initial_referring_domain".into(), initial_domain);
+    }
+
+    if client.screen_height > 0 && client.screen_width > 0 {
+        props.insert("$screen_height".into(), client.screen_height.to_string());
+        props.insert("$screen_width".into(), client.screen_width.to_string());
+    }
+
+    props.insert("mp_lib".into(), "web".into());
+    props.insert("$lib_version".into(), lib_version.into());
+    insert_if_nonempty(props, "$device_id", &context.user.edgee_id);
+
+    // Not stored by Mixpanel: `/import` only uses it to geolocate the event,
+    // the same way it geolocates browser requests sent by mixpanel-js.
+    insert_if_nonempty(props, "ip", &client.ip);
+
+    // mixpanel-js only sends the five classic UTM parameters.
+    let utm = UtmParams::resolve(&context.campaign, page);
+    UtmParams {
+        id: String::new(),
+        creative_format: String::new(),
+        marketing_tactic: String::new(),
+        ..utm
+    }
+    .enrich(props, UtmNaming::Standard);
+    click_ids::enrich(props, page);
+
+    if matches!(event.data, Data::Page(_)) {
+        insert_if_nonempty(props, "current_page_title", &page.title);
+        insert_if_nonempty(props, "current_url_path", &page.path);
+        insert_if_nonempty(props, "current_url_search", &page.search);
+        if let Some(domain) = url_host(&page.url) {
+            props.insert("current_domain".into(), domain);
+        }
+        if let Some((protocol, _)) = page.url.split_once("://") {
+            props.insert("current_url_protocol".into(), format!("{protocol}:"));
+        }
+    }
+}
+
+/// Adds the properties mixpanel-js sets on profiles with `people.set`.
+pub fn enrich_profile(props: &mut HashMap<String, String>, event: &Event) {
+    let client = &event.context.client;
+    let user_agent = user_agent::parse_client(client);
+
+    if let Some(os) = os_name(client, &user_agent) {
+        props.insert("$os".into(), os);
+    }
+    if let Some(browser) = user_agent.browser {
+        props.insert("$browser".into(), browser);
+    }
+}
+
+fn enrich_device(props: &mut HashMap<String, String>, client: &Client) {
+    let user_agent = user_agent::parse_client(client);
+
+    if let Some(device) = user_agent.device_name() {
+        props.insert("$device".into(), device);
+    }
+    if let Some(os) = os_name(client, &user_agent) {
+        props.insert("$os".into(), os);
+    }
+    if let Some(browser) = user_agent.browser {
+        props.insert("$browser".into(), browser);
+    }
+    if let Some(version) = user_agent.browser_version {
+        props.insert("$browser_version".into(), version);
+    }
+}
+
+/// The OS reported by Edgee wins over the one parsed from the user agent.
+fn os_name(client: &Client, user_agent: &UserAgent) -> Option<String> {
+    if client.os_name.trim().is_empty() {
+        user_agent.os.clone()
+    } else {
+        Some(client.os_name.clone())
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+/// Root of namespaced context properties.
+const ROOT: &str = "edgee";
+
+/// How page, campaign, session and client enrichment is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextNamespace {
+    /// Context properties are sent at the top level.
+    None,
+    /// Context properties are prefixed with their group, e.g. `edgee.client.locale`.
+    Prefix,
+    /// Context properties are sent as nested objects, e.g. `{"edgee": {"client": {"locale": ...}}}`.
+    Nested,
+}
+
+impl ContextNamespace {
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        match settings_map
+            .get("context_namespace")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("none") => Ok(ContextNamespace::None),
+            Some("prefix") => Ok(ContextNamespace::Prefix),
+            Some("nested") => Ok(ContextNamespace::Nested),
+            Some(other) => Err(anyhow::anyhow!(
+                "Invalid 'context_namespace' setting: '{other}' (expected 'none', 'prefix' or 'nested')"
+            )),
+        }
+    }
+
+    /// Adds the properties of a context `group` (`page`, `campaign`, `session`
+    /// or `client`) to `props`. Mixpanel reserved properties stay at the top level.
+    pub fn extend(
+        &self,
+        props: &mut HashMap<String, String>,
+        group: &str,
+        group_props: HashMap<String, String>,
+    ) {
+        for (key, value) in group_props {
+            if *self == ContextNamespace::None || is_reserved(&key) {
+                props.insert(key, value);
+            } else {
+                props.insert(format!("{ROOT}.{group}.{key}"), value);
+            }
+        }
+    }
+
+    /// Converts properties to the JSON sent to Mixpanel. In nested mode,
+    /// `edgee.<group>.<key>` properties become nested objects. A flat
+    /// `edgee.<group>` (or `edgee`) value sharing its name with an object is
+    /// kept in that object as `_value`, so neither value is lost.
+    pub fn to_json(
+        self,
+        props: HashMap<String, String>,
+    ) -> serde_json::Map<String, serde_json::Value> {
+        let mut json = serde_json::Map::new();
+        let mut nested = serde_json::Map::new();
+        let mut flat = Vec::new();
+
+        for (key, value) in props {
+            let path = match self {
+                ContextNamespace::Nested => key
+                    .strip_prefix(ROOT)
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    .filter(|rest| !rest.is_empty()),
+                _ => None,
+            };
+
+            match path.map(|path| path.split_once('.').ok_or(path)) {
+                Some(Ok((group, leaf))) => {
+                    if let Some(object) = nested
+                        .entry(group)
+                        .or_insert_with(|| serde_json::Value::Object(Default::default()))
+                        .as_object_mut()
+                    {
+                        object.insert(leaf.to_string(), value.into());
+                    }
+                }
+                Some(Err(leaf)) => flat.push((leaf.to_string(), value)),
+                None => {
+                    json.insert(key, value.into());
+                }
+            }
+        }
+
+        // Groups are all known now, so conflicts do not depend on the order.
+        for (leaf, value) in flat {
+            insert_flat(&mut nested, leaf, value);
+        }
+        if !nested.is_empty() {
+            if let Some(serde_json::Value::String(value)) = json.remove(ROOT) {
+                insert_flat(&mut nested, CONFLICT_KEY.into(), value);
+            }
+            json.insert(ROOT.into(), nested.into());
+        }
+        json
+    }
+}
+
+/// Where a flat value goes when an object has the same name.
+const CONFLICT_KEY: &str = "_value";
+
+/// Inserts `value` under `key`, or under the `_value` key of the object
+/// already named `key`.
+fn insert_flat(
+    object: &mut serde_json::Map<String, serde_json::Value>,
+    key: String,
+    value: String,
+) {
+    match object.get_mut(&key).and_then(|v| v.as_object_mut()) {
+        Some(group) => {
+            group.insert(CONFLICT_KEY.into(), value.into());
+        }
+        None => {
+            object.insert(key, value.into());
+        }
+    }
+}
+
+/// `$`/`mp_` properties and `ip` drive Mixpanel's own processing and reports.
+fn is_reserved(key: &str) -> bool {
+    key.starts_with('$') || key.starts_with("mp_") || key == "ip"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_props() -> HashMap<String, String> {
+        HashMap::from([
+            ("$os".to_string(), "Mac OS X".to_string()),
+            ("ip".to_string(), "192.168.0.1".to_string()),
+            ("locale".to_string(), "fr-FR".to_string()),
+        ])
+    }
+
+    #[test]
+    fn prefixes_non_reserved_properties() {
+        let mut props = HashMap::new();
+        ContextNamespace::Prefix.extend(&mut props, "client", client_props());
+        assert_eq!(props.get("$os"), Some(&"Mac OS X".to_string()));
+        assert_eq!(props.get("ip"), Some(&"192.168.0.1".to_string()));
+        assert_eq!(props.get("edgee.client.locale"), Some(&"fr-FR".to_string()));
+        assert!(!props.contains_key("locale"));
+
+        let mut props = HashMap::new();
+        ContextNamespace::None.extend(&mut props, "client", client_props());
+        assert_eq!(props, client_props());
+    }
+
+    #[test]
+    fn nests_namespaced_properties() {
+        let mut props = HashMap::from([("plan".to_string(), "pro".to_string())]);
+        ContextNamespace::Nested.extend(&mut props, "client", client_props());
+        ContextNamespace::Nested.extend(
+            &mut props,
+            "session",
+            HashMap::from([("session_count".to_string(), "2".to_string())]),
+        );
+        props.insert("edgee.title".to_string(), "page title".to_string());
+
+        let json = serde_json::Value::Object(ContextNamespace::Nested.to_json(props));
+        assert_eq!(json["plan"], "pro");
+        assert_eq!(json["$os"], "Mac OS X");
+        assert_eq!(json["edgee"]["client"]["locale"], "fr-FR");
+        assert_eq!(json["edgee"]["session"]["session_count"], "2");
+        assert_eq!(json["edgee"]["title"], "page title");
+    }
+
+    #[test]
+    fn keeps_flat_values_sharing_a_group_name() {
+        // Each map gets a new hash seed, so iteration orders vary.
+        for _ in 0..10 {
+            let props = HashMap::from([
+                ("edgee".to_string(), "root".to_string()),
+                ("edgee.page".to_string(), "pricing".to_string()),
+                ("edgee.page.title".to_string(), "page title".to_string()),
+            ]);
+            let json = serde_json::Value::Object(ContextNamespace::Nested.to_json(props));
+            assert_eq!(json["edgee"]["page"]["title"], "page title");
+            assert_eq!(json["edgee"]["page"]["_value"], "pricing");
+            assert_eq!(json["edgee"]["_value"], "root");
+        }
+    }
+
+    #[test]
+    fn prefix_mode_keeps_flat_json() {
+        let props = HashMap::from([("edgee.client.locale".to_string(), "fr-FR".to_string())]);
+        let json = ContextNamespace::Prefix.to_json(props);
+        assert_eq!(json["edgee.client.locale"], "fr-FR");
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+/// Prefix of context properties moved aside by [`PropertyPrecedence::Namespace`].
+pub const CONTEXT_NAMESPACE: &str = "edgee.";
+
+/// Which value is kept when an event property and a context enricher
+/// (page, campaign, session, client) produce the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyPrecedence {
+    /// The event's own property is kept and the context value dropped.
+    Event,
+    /// The context value overwrites the event property.
+    Context,
+    /// The event's own property is kept and the context value is sent
+    /// under `edgee.<key>`.
+    Namespace,
+}
+
+impl PropertyPrecedence {
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        match settings_map
+            .get("property_precedence")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("context") => Ok(PropertyPrecedence::Context),
+            Some("event") => Ok(PropertyPrecedence::Event),
+            Some("namespace") => Ok(PropertyPrecedence::Namespace),
+            Some(other) => Err(anyhow::anyhow!(
+                "Invalid 'property_precedence' setting: '{other}' (expected 'event', 'context' or 'namespace')"
+            )),
+        }
+    }
+
+    /// Merges context properties into event properties. Returns the merged
+    /// properties and the sorted keys whose event and context values differed.
+    pub fn merge(
+        &self,
+        mut event: HashMap<String, String>,
+        context: HashMap<String, String>,
+    ) -> (HashMap<String, String>, Vec<String>) {
+        let mut collisions = Vec::new();
+
+        for (key, value) in context {
+            match event.get(&key) {
+                None => {
+                    event.insert(key, value);
+                }
+                Some(existing) if *existing == value => {}
+                Some(_) => {
+                    collisions.push(key.clone());
+                    match self {
+                        PropertyPrecedence::Event => {}
+                        PropertyPrecedence::Context => {
+                            event.insert(key, value);
+                        }
+                        PropertyPrecedence::Namespace => {
+                            event.insert(format!("{CONTEXT_NAMESPACE}{key}"), value);
+                        }
+                    }
+                }
+            }
+        }
+
+        collisions.sort();
+        (event, collisions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn props(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn merge_policies() {
+        let event = props(&[("name", "Checkout"), ("path", "/cart")]);
+        let context = props(&[("name", "Cart page"), ("path", "/cart"), ("title", "Cart")]);
+
+        let (merged, collisions) = PropertyPrecedence::Event.merge(event.clone(), context.clone());
+        assert_eq!(
+            merged,
+            props(&[("name", "Checkout"), ("path", "/cart"), ("title", "Cart")])
+        );
+        assert_eq!(collisions, vec!["name".to_string()]);
+
+        let (merged, _) = PropertyPrecedence::Context.merge(event.clone(), context.clone());
+        assert_eq!(merged.get("name"), Some(&"Cart page".to_string()));
+
+        let (merged, _) = PropertyPrecedence::Namespace.merge(event, context);
+        assert_eq!(merged.get("name"), Some(&"Checkout".to_string()));
+        assert_eq!(merged.get("edgee.name"), Some(&"Cart page".to_string()));
+        assert!(!merged.contains_key("edgee.path"));
+    }
+
+    #[test]
+    fn parses_setting() {
+        let settings = props(&[("property_precedence", " Namespace ")]);
+        assert_eq!(
+            PropertyPrecedence::from_settings(&settings).unwrap(),
+            PropertyPrecedence::Namespace
+        );
+        assert_eq!(
+            PropertyPrecedence::from_settings(&HashMap::new()).unwrap(),
+            PropertyPrecedence::Context
+        );
+        let invalid = props(&[("property_precedence", "page")]);
+        assert!(PropertyPrecedence::from_settings(&invalid).is_err());
+    }
+}
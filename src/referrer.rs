@@ -0,0 +1,210 @@
+use crate::helpers::{parse_query, url_host};
+use std::collections::HashMap;
+
+/// Search engines identified by the first label of their search host (so that
+/// every country domain matches), with the query parameters carrying the keyword.
+const SEARCH_ENGINES: &[(&str, &str, &[&str])] = &[
+    ("google", "google", &["q"]),
+    ("bing", "bing", &["q"]),
+    ("yahoo", "yahoo", &["p"]),
+    ("duckduckgo", "duckduckgo", &["q"]),
+    ("baidu", "baidu", &["wd", "word"]),
+    ("yandex", "yandex", &["text"]),
+    ("ecosia", "ecosia", &["q"]),
+    ("qwant", "qwant", &["q"]),
+    ("naver", "naver", &["query"]),
+    ("seznam", "seznam", &["q"]),
+    ("startpage", "startpage", &["query", "q"]),
+    ("brave", "search.brave", &["q"]),
+];
+
+/// Social networks identified by their domains (subdomains included).
+const SOCIAL_NETWORKS: &[(&str, &[&str])] = &[
+    ("facebook", &["facebook.com", "fb.com", "fb.me"]),
+    ("instagram", &["instagram.com"]),
+    ("twitter", &["twitter.com", "t.co", "x.com"]),
+    ("linkedin", &["linkedin.com", "lnkd.in"]),
+    ("pinterest", &["pinterest.com", "pin.it"]),
+    ("reddit", &["reddit.com", "redd.it"]),
+    ("youtube", &["youtube.com", "youtu.be"]),
+    ("tiktok", &["tiktok.com"]),
+    ("snapchat", &["snapchat.com"]),
+    ("threads", &["threads.net"]),
+    ("mastodon", &["mastodon.social"]),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Referrer {
+    pub domain: Option<String>,
+    pub search_engine: Option<&'static str>,
+    pub search_keyword: Option<String>,
+    pub social_network: Option<&'static str>,
+}
+
+impl Referrer {
+    pub fn parse(referrer: &str) -> Self {
+        let Some(domain) = url_host(referrer) else {
+            return Self::default();
+        };
+
+        let search = SEARCH_ENGINES
+            .iter()
+            .find(|(_, label, _)| is_search_host(&domain, label));
+        let search_keyword = search.and_then(|(_, _, params)| {
+            parse_query(referrer)
+                .into_iter()
+                .find(|(key, value)| params.contains(&key.as_str()) && !value.trim().is_empty())
+                .map(|(_, value)| value.trim().to_string())
+        });
+
+        let social_network = SOCIAL_NETWORKS
+            .iter()
+            .find(|(_, domains)| domains.iter().any(|d| host_matches(&domain, d)))
+            .map(|(name, _)| *name);
+
+        Self {
+            search_engine: search.map(|(name, _, _)| *name),
+            search_keyword,
+            social_network,
+            domain: Some(domain),
+        }
+    }
+
+    /// Adds `$referring_domain`, `$search_engine`, `mp_keyword` and `social_network`.
+    pub fn enrich(&self, props: &mut HashMap<String, String>) {
+        if let Some(domain) = &self.domain {
+            props.insert("$referring_domain".into(), domain.clone());
+        }
+        if let Some(engine) = self.search_engine {
+            props.insert("$search_engine".into(), engine.into());
+        }
+        if let Some(keyword) = &self.search_keyword {
+            props.insert("mp_keyword".into(), keyword.clone());
+        }
+        if let Some(network) = self.social_network {
+            props.insert("social_network".into(), network.into());
+        }
+    }
+}
+
+/// True when a `utm_source` value or hostname names a known search engine.
+pub fn is_search_source(source: &str) -> bool {
+    let source = source.trim().to_lowercase();
+    SEARCH_ENGINES
+        .iter()
+        .any(|(name, label, _)| source == *name || is_search_host(&source, label))
+}
+
+/// True when a `utm_source` value or hostname names a known social network.
+pub fn is_social_source(source: &str) -> bool {
+    let source = source.trim().to_lowercase();
+    SOCIAL_NETWORKS
+        .iter()
+        .any(|(name, domains)| source == *name || domains.iter().any(|d| host_matches(&source, d)))
+}
+
+/// True when `host` is the search host of the engine identified by `label`
+/// (which may span several labels, e.g. `search.brave`): `google.fr`,
+/// `www.google.fr` or `fr.search.yahoo.com`, but not `mail.google.com`.
+fn is_search_host(host: &str, label: &str) -> bool {
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let host = host.strip_prefix("m.").unwrap_or(host);
+    let host = match host.split_once('.') {
+        Some((country, rest)) if country.len() == 2 && rest.starts_with("search.") => rest,
+        _ => host,
+    };
+    let host = host
+        .strip_prefix("search.")
+        .filter(|rest| rest.starts_with(&format!("{label}.")))
+        .unwrap_or(host);
+
+    host == label || host.starts_with(&format!("{label}."))
+}
+
+/// Whether `host` is `domain` or one of its subdomains.
+pub fn host_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{domain}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_search_engine_and_keyword() {
+        let referrer = Referrer::parse("https://www.google.co.uk/search?hl=en&q=edgee+mixpanel");
+        assert_eq!(referrer.domain.as_deref(), Some("www.google.co.uk"));
+        assert_eq!(referrer.search_engine, Some("google"));
+        assert_eq!(referrer.search_keyword.as_deref(), Some("edgee mixpanel"));
+        assert_eq!(referrer.social_network, None);
+
+        let yahoo = Referrer::parse("https://search.yahoo.com/search?p=edge%20analytics");
+        assert_eq!(yahoo.search_engine, Some("yahoo"));
+        assert_eq!(yahoo.search_keyword.as_deref(), Some("edge analytics"));
+
+        let naver = Referrer::parse("https://m.search.naver.com/search.naver?query=edgee");
+        assert_eq!(naver.search_engine, Some("naver"));
+        assert_eq!(
+            Referrer::parse("https://fr.search.yahoo.com/search?p=edgee").search_engine,
+            Some("yahoo")
+        );
+        assert_eq!(
+            Referrer::parse("https://search.brave.com/search?q=edgee").search_engine,
+            Some("brave")
+        );
+
+        let no_keyword = Referrer::parse("https://www.bing.com/");
+        assert_eq!(no_keyword.search_engine, Some("bing"));
+        assert_eq!(no_keyword.search_keyword, None);
+    }
+
+    #[test]
+    fn ignores_non_search_services_of_search_engines() {
+        for url in [
+            "https://mail.google.com/mail/u/0/?q=invoice",
+            "https://docs.google.com/document/d/1",
+            "https://mail.yahoo.com/d/folders/1",
+            "https://blog.naver.com/edgee",
+        ] {
+            let referrer = Referrer::parse(url);
+            assert_eq!(referrer.search_engine, None, "{url}");
+            assert_eq!(referrer.search_keyword, None, "{url}");
+        }
+        assert!(!is_search_source("mail.google.com"));
+    }
+
+    #[test]
+    fn detects_social_networks() {
+        assert_eq!(
+            Referrer::parse("https://l.facebook.com/l.php?u=x").social_network,
+            Some("facebook")
+        );
+        assert_eq!(
+            Referrer::parse("https://t.co/abc").social_network,
+            Some("twitter")
+        );
+        assert_eq!(
+            Referrer::parse("https://notfacebook.com/").social_network,
+            None
+        );
+    }
+
+    #[test]
+    fn classifies_source_names() {
+        assert!(is_search_source("Google"));
+        assert!(is_search_source("www.bing.com"));
+        assert!(is_social_source("facebook"));
+        assert!(is_social_source("m.facebook.com"));
+        assert!(!is_social_source("newsletter"));
+    }
+
+    #[test]
+    fn plain_referrers_only_have_a_domain() {
+        let referrer = Referrer::parse("https://blog.example.com/post?q=ignored");
+        assert_eq!(referrer.domain.as_deref(), Some("blog.example.com"));
+        assert_eq!(referrer.search_engine, None);
+        assert_eq!(referrer.search_keyword, None);
+
+        assert_eq!(Referrer::parse(""), Referrer::default());
+    }
+}
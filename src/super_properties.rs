@@ -0,0 +1,169 @@
+use crate::helpers::{parse_bool, parse_list};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuperPropertyPrecedence {
+    /// Properties already on the event are kept.
+    Event,
+    /// Super properties overwrite colliding event properties.
+    Super,
+}
+
+/// Constant properties (`environment`, `site`, `app_version`...) added to
+/// every event, and optionally `$set` on profiles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuperProperties {
+    pub properties: Vec<(String, String)>,
+    pub on_profile: bool,
+    pub precedence: SuperPropertyPrecedence,
+}
+
+impl SuperProperties {
+    /// Reads `super_properties`, either a JSON object or a comma or newline
+    /// separated list of `key=value` pairs, along with
+    /// `super_properties_on_profile` and `super_properties_precedence`.
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let value = settings_map.get("super_properties");
+        let properties = match value.map(|v| v.trim()) {
+            Some(json) if json.starts_with('{') => parse_json(json)?,
+            _ => parse_pairs(value)?,
+        };
+
+        let on_profile = parse_bool(
+            settings_map.get("super_properties_on_profile"),
+            "super_properties_on_profile",
+        )?;
+
+        let precedence = match settings_map
+            .get("super_properties_precedence")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("event") => SuperPropertyPrecedence::Event,
+            Some("super") => SuperPropertyPrecedence::Super,
+            Some(other) => {
+                return Err(anyhow::anyhow!(
+                    "Invalid 'super_properties_precedence' setting: '{other}' (expected 'event' or 'super')"
+                ))
+            }
+        };
+
+        Ok(Self {
+            properties,
+            on_profile,
+            precedence,
+        })
+    }
+
+    /// Merges the super properties into `props` according to the precedence.
+    pub fn apply(&self, props: &mut HashMap<String, String>) {
+        for (key, value) in &self.properties {
+            match self.precedence {
+                SuperPropertyPrecedence::Event => {
+                    props.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+                SuperPropertyPrecedence::Super => {
+                    props.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+fn parse_json(json: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("Invalid 'super_properties' setting: {e}"))?;
+
+    Ok(object
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(value) => Some((key, value)),
+            other => Some((key, other.to_string())),
+        })
+        .collect())
+}
+
+fn parse_pairs(list: Option<&String>) -> anyhow::Result<Vec<(String, String)>> {
+    parse_list(list)
+        .into_iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(anyhow::anyhow!(
+                "Invalid 'super_properties' setting: '{pair}' (expected key=value)"
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_pairs_and_json() {
+        let pairs = SuperProperties::from_settings(&settings(&[(
+            "super_properties",
+            "environment=staging, site=fr-shop\napp_version=2.3.0",
+        )]))
+        .unwrap();
+        assert_eq!(
+            pairs.properties,
+            vec![
+                ("environment".to_string(), "staging".to_string()),
+                ("site".to_string(), "fr-shop".to_string()),
+                ("app_version".to_string(), "2.3.0".to_string()),
+            ]
+        );
+
+        let json = SuperProperties::from_settings(&settings(&[(
+            "super_properties",
+            r#"{"environment": "staging", "beta": true, "build": 42, "unset": null}"#,
+        )]))
+        .unwrap();
+        let props: HashMap<_, _> = json.properties.into_iter().collect();
+        assert_eq!(props.get("beta"), Some(&"true".to_string()));
+        assert_eq!(props.get("build"), Some(&"42".to_string()));
+        assert!(!props.contains_key("unset"));
+    }
+
+    #[test]
+    fn rejects_malformed_settings() {
+        for (key, value) in [
+            ("super_properties", "environment"),
+            ("super_properties", "{\"a\": "),
+            ("super_properties_precedence", "context"),
+        ] {
+            assert!(SuperProperties::from_settings(&settings(&[(key, value)])).is_err());
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        let mut super_properties = SuperProperties::from_settings(&settings(&[(
+            "super_properties",
+            "environment=production,site=fr-shop",
+        )]))
+        .unwrap();
+        let event = HashMap::from([("environment".to_string(), "staging".to_string())]);
+
+        let mut props = event.clone();
+        super_properties.apply(&mut props);
+        assert_eq!(props.get("environment"), Some(&"staging".to_string()));
+        assert_eq!(props.get("site"), Some(&"fr-shop".to_string()));
+
+        super_properties.precedence = SuperPropertyPrecedence::Super;
+        let mut props = event;
+        super_properties.apply(&mut props);
+        assert_eq!(props.get("environment"), Some(&"production".to_string()));
+    }
+}
@@ -0,0 +1,302 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanMode {
+    /// Violations are listed in `$schema_violations`.
+    Warn,
+    /// Properties missing from the plan are removed, other violations are listed.
+    Strip,
+    /// Events with violations are rejected.
+    Block,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PropertyType {
+    #[default]
+    Any,
+    String,
+    Number,
+    Integer,
+    Boolean,
+}
+
+impl PropertyType {
+    fn accepts(&self, value: &str) -> bool {
+        match self {
+            PropertyType::Any | PropertyType::String => true,
+            PropertyType::Number => value.trim().parse::<f64>().is_ok_and(f64::is_finite),
+            PropertyType::Integer => value.trim().parse::<i64>().is_ok(),
+            PropertyType::Boolean => matches!(value.trim(), "true" | "false"),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            PropertyType::Any => "any",
+            PropertyType::String => "string",
+            PropertyType::Number => "number",
+            PropertyType::Integer => "integer",
+            PropertyType::Boolean => "boolean",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PropertySchema {
+    #[serde(default, rename = "type")]
+    pub kind: PropertyType,
+    #[serde(default)]
+    pub required: bool,
+    /// Allowed values. Any value is allowed when empty.
+    #[serde(default, rename = "enum")]
+    pub allowed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventSchema {
+    #[serde(default)]
+    pub properties: HashMap<String, PropertySchema>,
+    #[serde(default = "default_true")]
+    pub additional_properties: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlanDefinition {
+    events: HashMap<String, EventSchema>,
+    #[serde(default = "default_true")]
+    allow_unplanned_events: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Expected properties of each event, validated against the properties
+/// sent by `page` and `track` events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackingPlan {
+    pub events: HashMap<String, EventSchema>,
+    pub allow_unplanned_events: bool,
+    pub mode: PlanMode,
+}
+
+impl TrackingPlan {
+    /// Reads the `tracking_plan` JSON setting and `tracking_plan_mode`.
+    /// Returns `None` when no tracking plan is configured.
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Option<Self>> {
+        let Some(json) = settings_map
+            .get("tracking_plan")
+            .filter(|v| !v.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+
+        let definition: PlanDefinition = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("Invalid 'tracking_plan' setting: {e}"))?;
+
+        let mode = match settings_map
+            .get("tracking_plan_mode")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("warn") => PlanMode::Warn,
+            Some("strip") => PlanMode::Strip,
+            Some("block") => PlanMode::Block,
+            Some(other) => {
+                return Err(anyhow::anyhow!(
+                    "Invalid 'tracking_plan_mode' setting: '{other}' (expected 'warn', 'strip' or 'block')"
+                ))
+            }
+        };
+
+        Ok(Some(Self {
+            events: definition.events,
+            allow_unplanned_events: definition.allow_unplanned_events,
+            mode,
+        }))
+    }
+
+    /// Lists the ways `props` of event `name` depart from the plan, in a stable order.
+    pub fn violations(&self, name: &str, props: &HashMap<String, String>) -> Vec<String> {
+        let Some(schema) = self.events.get(name) else {
+            return if self.allow_unplanned_events {
+                Vec::new()
+            } else {
+                vec![format!("unplanned event '{name}'")]
+            };
+        };
+
+        let mut planned: Vec<(&String, &PropertySchema)> = schema.properties.iter().collect();
+        planned.sort_by_key(|(key, _)| *key);
+
+        let mut violations = Vec::new();
+        for (key, property) in planned {
+            match props.get(key) {
+                None if property.required => {
+                    violations.push(format!("missing required property '{key}'"))
+                }
+                None => {}
+                Some(value) if !property.kind.accepts(value) => violations.push(format!(
+                    "property '{key}' should be of type {}",
+                    property.kind.as_str()
+                )),
+                Some(value)
+                    if !property.allowed.is_empty() && !property.allowed.contains(value) =>
+                {
+                    violations.push(format!(
+                        "property '{key}' should be one of {}",
+                        property.allowed.join(", ")
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+
+        if !schema.additional_properties {
+            let mut unknown: Vec<&String> = props
+                .keys()
+                .filter(|key| !schema.properties.contains_key(*key))
+                .collect();
+            unknown.sort();
+            violations.extend(
+                unknown
+                    .into_iter()
+                    .map(|key| format!("unexpected property '{key}'")),
+            );
+        }
+
+        violations
+    }
+
+    /// Validates the properties of event `name` according to the mode.
+    /// Returns an error when a blocked event must not be sent.
+    pub fn apply(&self, name: &str, props: &mut HashMap<String, String>) -> Result<(), String> {
+        if self.mode == PlanMode::Strip {
+            if let Some(schema) = self.events.get(name) {
+                if !schema.additional_properties {
+                    props.retain(|key, _| schema.properties.contains_key(key));
+                }
+            }
+        }
+
+        let violations = self.violations(name, props);
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        match self.mode {
+            PlanMode::Block => Err(format!(
+                "Event blocked by tracking plan: {}",
+                violations.join("; ")
+            )),
+            PlanMode::Warn | PlanMode::Strip => {
+                if let Ok(report) = serde_json::to_string(&violations) {
+                    props.insert("$schema_violations".into(), report);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = r#"{
+        "events": {
+            "Signup": {
+                "properties": {
+                    "plan": {"type": "string", "required": true, "enum": ["free", "pro"]},
+                    "seats": {"type": "integer"}
+                },
+                "additional_properties": false
+            },
+            "Page View": {}
+        },
+        "allow_unplanned_events": false
+    }"#;
+
+    fn plan(mode: &str) -> TrackingPlan {
+        let settings = HashMap::from([
+            ("tracking_plan".to_string(), PLAN.to_string()),
+            ("tracking_plan_mode".to_string(), mode.to_string()),
+        ]);
+        TrackingPlan::from_settings(&settings).unwrap().unwrap()
+    }
+
+    fn props(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn reports_violations() {
+        let plan = plan("warn");
+        assert!(plan
+            .violations("Signup", &props(&[("plan", "pro"), ("seats", "3")]))
+            .is_empty());
+        assert!(plan
+            .violations("Page View", &props(&[("anything", "x")]))
+            .is_empty());
+
+        assert_eq!(
+            plan.violations(
+                "Signup",
+                &props(&[("plan", "gold"), ("seats", "three"), ("coupon", "x")])
+            ),
+            vec![
+                "property 'plan' should be one of free, pro".to_string(),
+                "property 'seats' should be of type integer".to_string(),
+                "unexpected property 'coupon'".to_string(),
+            ]
+        );
+        assert_eq!(
+            plan.violations("Signup", &HashMap::new()),
+            vec!["missing required property 'plan'".to_string()]
+        );
+        assert_eq!(
+            plan.violations("Login", &HashMap::new()),
+            vec!["unplanned event 'Login'".to_string()]
+        );
+    }
+
+    #[test]
+    fn modes() {
+        let event = props(&[("plan", "pro"), ("coupon", "x")]);
+
+        let mut warned = event.clone();
+        plan("warn").apply("Signup", &mut warned).unwrap();
+        assert_eq!(
+            warned.get("$schema_violations"),
+            Some(&r#"["unexpected property 'coupon'"]"#.to_string())
+        );
+
+        let mut stripped = event.clone();
+        plan("strip").apply("Signup", &mut stripped).unwrap();
+        assert_eq!(stripped, props(&[("plan", "pro")]));
+
+        let mut blocked = event;
+        assert!(plan("block").apply("Signup", &mut blocked).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_plans() {
+        assert_eq!(TrackingPlan::from_settings(&HashMap::new()).unwrap(), None);
+        for plan in [
+            r#"{"events": {"Signup": {"properties": {"plan": {"type": "date"}}}}}"#,
+            r#"{"events": {}, "unknown": true}"#,
+            "[",
+        ] {
+            let settings = HashMap::from([("tracking_plan".to_string(), plan.to_string())]);
+            assert!(TrackingPlan::from_settings(&settings).is_err());
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::exports::edgee::components::data_collection::EdgeeRequest;
+
+/// Bodies smaller than this are not worth compressing.
+const DEFAULT_GZIP_THRESHOLD: usize = 1024;
+
+/// The channel carrying `EdgeeRequest`s to Mixpanel. No transport compresses
+/// bodies yet: this is the extension point for one that can carry binary bodies.
+pub trait Transport {
+    /// Returns `request` with its body gzip-compressed and a
+    /// `Content-Encoding: gzip` header, or the untouched request when the
+    /// transport cannot carry binary bodies.
+    fn gzip(&self, request: EdgeeRequest) -> Result<EdgeeRequest, EdgeeRequest>;
+}
+
+/// The Edgee data collection host. `EdgeeRequest.body` is a WIT `string`,
+/// so bodies must be valid UTF-8 and gzip output cannot be carried: requests
+/// are always sent uncompressed.
+pub struct EdgeeHost;
+
+impl Transport for EdgeeHost {
+    fn gzip(&self, request: EdgeeRequest) -> Result<EdgeeRequest, EdgeeRequest> {
+        Err(request)
+    }
+}
+
+/// Gzip compression of request bodies. It is not exposed as a setting: no
+/// transport can carry compressed bodies yet, so it stays disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyCompression {
+    pub enabled: bool,
+    /// Minimum body size, in bytes, to compress.
+    pub threshold: usize,
+}
+
+impl Default for BodyCompression {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: DEFAULT_GZIP_THRESHOLD,
+        }
+    }
+}
+
+impl BodyCompression {
+    /// Hands bodies of at least `threshold` bytes over to `transport` for
+    /// compression when enabled. With [`EdgeeHost`], the only transport today,
+    /// the request is always sent unchanged.
+    pub fn apply(&self, request: EdgeeRequest, transport: &impl Transport) -> EdgeeRequest {
+        if !self.enabled || request.body.len() < self.threshold {
+            return request;
+        }
+        transport.gzip(request).unwrap_or_else(|request| request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exports::edgee::components::data_collection::HttpMethod;
+
+    /// Marks the requests handed over for compression. Nothing is compressed.
+    struct RecordingTransport;
+
+    impl Transport for RecordingTransport {
+        fn gzip(&self, mut request: EdgeeRequest) -> Result<EdgeeRequest, EdgeeRequest> {
+            request
+                .headers
+                .push(("X-Gzip-Requested".into(), "1".into()));
+            Ok(request)
+        }
+    }
+
+    fn request(body: &str) -> EdgeeRequest {
+        EdgeeRequest {
+            method: HttpMethod::Post,
+            url: "https://api.mixpanel.com/import".into(),
+            headers: vec![],
+            body: body.into(),
+            forward_client_headers: false,
+        }
+    }
+
+    fn handed_over(request: &EdgeeRequest) -> bool {
+        request
+            .headers
+            .iter()
+            .any(|(name, _)| name == "X-Gzip-Requested")
+    }
+
+    #[test]
+    fn only_hands_over_enabled_bodies_above_the_threshold() {
+        let compression = BodyCompression {
+            enabled: true,
+            threshold: 10,
+        };
+
+        assert!(handed_over(&compression.apply(
+            request("[{\"event\":\"Signup\"}]"),
+            &RecordingTransport
+        )));
+        assert!(!handed_over(
+            &compression.apply(request("[]"), &RecordingTransport)
+        ));
+
+        let disabled = BodyCompression {
+            enabled: false,
+            ..compression
+        };
+        assert!(!handed_over(&disabled.apply(
+            request("[{\"event\":\"Signup\"}]"),
+            &RecordingTransport
+        )));
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let body = "[{\"event\":\"Signup\"}]";
+        let sent = BodyCompression::default().apply(request(body), &RecordingTransport);
+        assert!(!handed_over(&sent));
+    }
+
+    #[test]
+    fn edgee_host_never_compresses() {
+        let compression = BodyCompression {
+            enabled: true,
+            threshold: 0,
+        };
+        let body = "[{\"event\":\"Signup\"}]";
+        let sent = compression.apply(request(body), &EdgeeHost);
+        assert_eq!(sent.body, body);
+        assert!(sent.headers.is_empty());
+    }
+}
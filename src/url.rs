@@ -0,0 +1,196 @@
+use crate::exports::edgee::components::data_collection::PageData;
+use crate::helpers::{insert_if_nonempty, parse_list, percent_decode, url_host};
+use std::collections::HashMap;
+
+/// Query parameters stripped when the `strip_query_params` setting is not set or empty.
+const DEFAULT_SENSITIVE_PARAMS: &[&str] = &[
+    "token",
+    "access_token",
+    "id_token",
+    "refresh_token",
+    "auth_token",
+    "reset_token",
+    "reset_code",
+    "password",
+    "email",
+    "secret",
+    "api_key",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UrlParts {
+    pub host: String,
+    pub path: String,
+    pub query: String,
+    pub hash: String,
+}
+
+impl UrlParts {
+    pub fn parse(url: &str) -> Self {
+        let (url, hash) = url.split_once('#').unwrap_or((url, ""));
+        let (url, query) = url.split_once('?').unwrap_or((url, ""));
+        let path = match url.split_once("://") {
+            Some((_, rest)) => rest.find('/').map(|i| &rest[i..]).unwrap_or("/"),
+            None => url,
+        };
+
+        Self {
+            host: url_host(url).unwrap_or_default(),
+            path: path.to_string(),
+            query: query.to_string(),
+            hash: hash.to_string(),
+        }
+    }
+
+    /// Adds `current_url_host`, `current_url_path`, `current_url_query` and `current_url_hash`.
+    pub fn enrich(&self, props: &mut HashMap<String, String>) {
+        insert_if_nonempty(props, "current_url_host", &self.host);
+        insert_if_nonempty(props, "current_url_path", &self.path);
+        insert_if_nonempty(props, "current_url_query", &self.query);
+        insert_if_nonempty(props, "current_url_hash", &self.hash);
+    }
+}
+
+/// Removes sensitive query parameters (password reset tokens, emails...)
+/// from page URLs before they are sent to Mixpanel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryStripper {
+    pub params: Vec<String>,
+}
+
+impl QueryStripper {
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> Self {
+        let params = match settings_map
+            .get("strip_query_params")
+            .filter(|v| !v.trim().is_empty())
+        {
+            Some(value) if value.trim().eq_ignore_ascii_case("none") => Vec::new(),
+            Some(value) => parse_list(Some(value))
+                .into_iter()
+                .map(|param| param.to_lowercase())
+                .collect(),
+            None => DEFAULT_SENSITIVE_PARAMS
+                .iter()
+                .map(|param| param.to_string())
+                .collect(),
+        };
+
+        Self { params }
+    }
+
+    /// Strips sensitive parameters from the URL, referrer, path and search of `page`.
+    pub fn sanitize_page(&self, page: &mut PageData) {
+        page.url = self.strip(&page.url);
+        page.referrer = self.strip(&page.referrer);
+        page.path = self.strip(&page.path);
+        page.search = self.strip(&page.search);
+    }
+
+    /// Strips sensitive parameters from the query string of `url` and from
+    /// query-like fragments such as `#access_token=...`. The remaining
+    /// parameters keep their original encoding and order.
+    pub fn strip(&self, url: &str) -> String {
+        if self.params.is_empty() {
+            return url.to_string();
+        }
+
+        let (rest, hash) = match url.split_once('#') {
+            Some((rest, hash)) => (rest, Some(hash)),
+            None => (url, None),
+        };
+        let mut stripped = match rest.split_once('?') {
+            Some((base, query)) => {
+                let query = self.strip_pairs(query);
+                if query.is_empty() {
+                    base.to_string()
+                } else {
+                    format!("{base}?{query}")
+                }
+            }
+            None => rest.to_string(),
+        };
+
+        if let Some(hash) = hash {
+            let hash = if hash.contains('=') {
+                self.strip_pairs(hash)
+            } else {
+                hash.to_string()
+            };
+            if !hash.is_empty() {
+                stripped.push('#');
+                stripped.push_str(&hash);
+            }
+        }
+
+        stripped
+    }
+
+    fn strip_pairs(&self, query: &str) -> String {
+        query
+            .split('&')
+            .filter(|pair| {
+                let key = pair.split('=').next().unwrap_or_default();
+                !self.params.contains(&percent_decode(key).to_lowercase())
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decomposes_urls() {
+        assert_eq!(
+            UrlParts::parse("https://Shop.example.com/cart/items?id=1&ref=mail#summary"),
+            UrlParts {
+                host: "shop.example.com".into(),
+                path: "/cart/items".into(),
+                query: "id=1&ref=mail".into(),
+                hash: "summary".into(),
+            }
+        );
+        assert_eq!(UrlParts::parse("https://example.com").path, "/");
+    }
+
+    #[test]
+    fn strips_default_sensitive_params() {
+        let stripper = QueryStripper::from_settings(&HashMap::new());
+        assert_eq!(
+            stripper.strip("https://example.com/reset?reset_token=s3cr3t&lang=fr#step"),
+            "https://example.com/reset?lang=fr#step"
+        );
+        assert_eq!(
+            stripper.strip("https://example.com/login?Email=a%40b.c"),
+            "https://example.com/login"
+        );
+        assert_eq!(
+            stripper.strip("https://example.com/callback#access_token=abc&state=xyz"),
+            "https://example.com/callback#state=xyz"
+        );
+        assert_eq!(stripper.strip("?token=abc"), "");
+    }
+
+    #[test]
+    fn configured_params_replace_defaults() {
+        let settings = HashMap::from([(
+            "strip_query_params".to_string(),
+            "invite, Coupon".to_string(),
+        )]);
+        let stripper = QueryStripper::from_settings(&settings);
+        assert_eq!(
+            stripper.strip("https://example.com/?invite=x&coupon=y&token=z"),
+            "https://example.com/?token=z"
+        );
+
+        let empty = HashMap::from([("strip_query_params".to_string(), " ".to_string())]);
+        let stripper = QueryStripper::from_settings(&empty);
+        assert_eq!(stripper.strip("/?token=z&page=2"), "/?page=2");
+
+        let disabled = HashMap::from([("strip_query_params".to_string(), "None".to_string())]);
+        let stripper = QueryStripper::from_settings(&disabled);
+        assert_eq!(stripper.strip("/?token=z"), "/?token=z");
+    }
+}
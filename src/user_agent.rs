@@ -0,0 +1,846 @@
+use crate::exports::edgee::components::data_collection::Client;
+use crate::filters::is_known_bot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceType {
+    Desktop,
+    Mobile,
+    Tablet,
+    Bot,
+}
+
+impl DeviceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceType::Desktop => "desktop",
+            DeviceType::Mobile => "mobile",
+            DeviceType::Tablet => "tablet",
+            DeviceType::Bot => "bot",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAgent {
+    pub browser: Option<String>,
+    pub browser_version: Option<String>,
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+    pub device_type: DeviceType,
+    pub device_vendor: Option<String>,
+    pub device_model: Option<String>,
+}
+
+impl UserAgent {
+    /// Device family as reported by mixpanel-js in `$device`.
+    pub fn device_name(&self) -> Option<String> {
+        match self.os.as_deref() {
+            Some("iOS") => self.device_model.clone(),
+            Some("Android") => Some("Android".into()),
+            Some("Windows Phone") => Some("Windows Phone".into()),
+            Some("Chrome OS") => Some("Chrome OS".into()),
+            _ => None,
+        }
+    }
+}
+
+/// Where a rule reads its version from.
+enum Version {
+    None,
+    /// Version immediately follows the token, e.g. `Firefox/121.0`.
+    After(&'static str),
+    /// Version follows the token and one more word, e.g. `CrOS x86_64 14541.0.0`.
+    AfterWord(&'static str),
+}
+
+/// A user agent rule matches when every `requires` fragment is present and,
+/// for versioned rules, the version token is found at a word boundary.
+struct Rule {
+    name: &'static str,
+    requires: &'static [&'static str],
+    version: Version,
+}
+
+const fn rule(name: &'static str, requires: &'static [&'static str], version: Version) -> Rule {
+    Rule {
+        name,
+        requires,
+        version,
+    }
+}
+
+/// Browser rules, most specific first: in-app webviews and Chromium derivatives
+/// must be matched before the generic Chrome and Safari tokens they also carry.
+const BROWSER_RULES: &[Rule] = &[
+    rule("Facebook Mobile", &[], Version::After("FBAV/")),
+    rule("Facebook Mobile", &["FBAN/"], Version::None),
+    rule("Instagram", &[], Version::After("Instagram ")),
+    rule("TikTok", &[], Version::After("musical_ly_")),
+    rule("Snapchat", &[], Version::After("Snapchat/")),
+    rule("LINE", &[], Version::After("Line/")),
+    rule("Samsung Internet", &[], Version::After("SamsungBrowser/")),
+    rule("Opera Mini", &[], Version::After("Opera Mini/")),
+    rule("Opera", &[], Version::After("OPR/")),
+    rule("Opera", &[], Version::After("OPiOS/")),
+    rule("Opera", &["Opera/"], Version::After("Version/")),
+    rule("Microsoft Edge", &[], Version::After("Edg/")),
+    rule("Microsoft Edge", &[], Version::After("Edge/")),
+    rule("Microsoft Edge", &[], Version::After("EdgA/")),
+    rule("Microsoft Edge", &[], Version::After("EdgiOS/")),
+    rule("Yandex", &[], Version::After("YaBrowser/")),
+    rule("Vivaldi", &[], Version::After("Vivaldi/")),
+    rule("Brave", &[], Version::After("Brave/")),
+    rule("UC Browser", &[], Version::After("UCBrowser/")),
+    rule("DuckDuckGo", &[], Version::After("DuckDuckGo/")),
+    rule("Amazon Silk", &[], Version::After("Silk/")),
+    rule("Headless Chrome", &[], Version::After("HeadlessChrome/")),
+    rule("Chrome iOS", &[], Version::After("CriOS/")),
+    rule("Firefox iOS", &[], Version::After("FxiOS/")),
+    rule("Android WebView", &["; wv)"], Version::After("Chrome/")),
+    rule("Firefox", &[], Version::After("Firefox/")),
+    rule("Chromium", &[], Version::After("Chromium/")),
+    rule("Chrome", &[], Version::After("Chrome/")),
+    rule("Internet Explorer", &[], Version::After("MSIE ")),
+    rule("Internet Explorer", &["Trident/"], Version::After("rv:")),
+    rule(
+        "Android Mobile",
+        &["Android", "Safari/"],
+        Version::After("Version/"),
+    ),
+    rule(
+        "Mobile Safari",
+        &["Mobile/", "Safari/"],
+        Version::After("Version/"),
+    ),
+    rule("Safari", &["Safari/"], Version::After("Version/")),
+    rule(
+        "iOS WebView",
+        &["iPhone", "AppleWebKit/", "Mobile/"],
+        Version::None,
+    ),
+    rule(
+        "iOS WebView",
+        &["iPad", "AppleWebKit/", "Mobile/"],
+        Version::None,
+    ),
+    rule(
+        "iOS WebView",
+        &["iPod", "AppleWebKit/", "Mobile/"],
+        Version::None,
+    ),
+];
+
+/// OS rules. Versions written with underscores (iOS, macOS) are normalised to dots.
+const OS_RULES: &[Rule] = &[
+    rule("Windows Phone", &[], Version::After("Windows Phone OS ")),
+    rule("Windows Phone", &[], Version::After("Windows Phone ")),
+    rule("Windows", &[], Version::After("Windows NT ")),
+    rule("iOS", &["iPhone"], Version::After(" OS ")),
+    rule("iOS", &["iPad"], Version::After(" OS ")),
+    rule("iOS", &["iPod"], Version::After(" OS ")),
+    rule("Android", &[], Version::After("Android ")),
+    rule("Chrome OS", &[], Version::AfterWord("CrOS ")),
+    rule("Mac OS X", &[], Version::After("Mac OS X ")),
+    rule("Linux", &["Linux"], Version::None),
+];
+
+/// Android model prefixes mapped to their vendor. In a prefix, `#` stands for
+/// any ASCII digit and `@` for any ASCII uppercase letter.
+const ANDROID_VENDORS: &[(&[&str], &str)] = &[
+    (&["SM-", "SAMSUNG", "GT-", "SCH-", "SGH-"], "Samsung"),
+    (&["Pixel"], "Google"),
+    (&["Redmi", "Mi ", "MI ", "M2###", "POCO"], "Xiaomi"),
+    (&["HUAWEI", "ELE-", "VOG-", "ANE-", "LYA-"], "Huawei"),
+    (
+        &["ONEPLUS", "OnePlus", "IN20", "LE21", "KB20", "NE22"],
+        "OnePlus",
+    ),
+    (&["moto", "Moto", "XT####"], "Motorola"),
+    (&["Nokia"], "Nokia"),
+    (&["LG-", "LM-"], "LG"),
+    (&["CPH#"], "OPPO"),
+    (&["RMX#"], "realme"),
+    (&["vivo", "V####"], "vivo"),
+    (&["KF@@"], "Amazon"),
+];
+
+pub fn parse(user_agent: &str) -> UserAgent {
+    let (browser, browser_version) = match_rules(BROWSER_RULES, user_agent);
+    let (os, os_version) = match_rules(OS_RULES, user_agent);
+    let os_version = os_version.map(|v| normalise_os_version(os.as_deref(), &v));
+    let (device_vendor, device_model) = parse_device(user_agent);
+
+    UserAgent {
+        browser,
+        browser_version,
+        device_type: parse_device_type(user_agent, os.as_deref()),
+        os,
+        os_version,
+        device_vendor,
+        device_model,
+    }
+}
+
+fn match_rules(rules: &[Rule], user_agent: &str) -> (Option<String>, Option<String>) {
+    for rule in rules {
+        if !rule.requires.iter().all(|r| user_agent.contains(r)) {
+            continue;
+        }
+
+        let version = match rule.version {
+            Version::None => Some(None),
+            Version::After(token) => find_token(user_agent, token).map(read_version),
+            Version::AfterWord(token) => find_token(user_agent, token).map(|rest| {
+                rest.split_once(' ')
+                    .and_then(|(_, rest)| read_version(rest))
+            }),
+        };
+
+        if let Some(version) = version {
+            return (Some(rule.name.to_string()), version);
+        }
+    }
+
+    (None, None)
+}
+
+/// Returns what follows the first occurrence of `token` that starts at a word
+/// boundary, preferring an occurrence followed by a version number.
+fn find_token<'a>(user_agent: &'a str, token: &str) -> Option<&'a str> {
+    let mut found = None;
+
+    for (index, _) in user_agent.match_indices(token) {
+        let at_boundary =
+            !token.starts_with(is_word_char) || !user_agent[..index].ends_with(is_word_char);
+        if !at_boundary {
+            continue;
+        }
+
+        let rest = &user_agent[index + token.len()..];
+        if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return Some(rest);
+        }
+        found.get_or_insert(rest);
+    }
+
+    found
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn read_version(rest: &str) -> Option<String> {
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_'))
+        .unwrap_or(rest.len());
+    let version = rest[..end].trim_end_matches(['.', '_']);
+
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.to_string())
+    }
+}
+
+fn normalise_os_version(os: Option<&str>, version: &str) -> String {
+    let version = version.replace('_', ".");
+    if os != Some("Windows") {
+        return version;
+    }
+
+    match version.as_str() {
+        "10.0" => "10",
+        "6.3" => "8.1",
+        "6.2" => "8",
+        "6.1" => "7",
+        "6.0" => "Vista",
+        "5.1" | "5.2" => "XP",
+        other => other,
+    }
+    .to_string()
+}
+
+fn parse_device_type(user_agent: &str, os: Option<&str>) -> DeviceType {
+    if is_known_bot(user_agent) {
+        return DeviceType::Bot;
+    }
+
+    let is_tablet = user_agent.contains("iPad")
+        || user_agent.contains("Tablet")
+        || user_agent.contains("Kindle")
+        || user_agent.contains("Silk/")
+        || (os == Some("Android") && !user_agent.contains("Mobile"));
+    if is_tablet {
+        return DeviceType::Tablet;
+    }
+
+    let is_mobile = user_agent.contains("Mobile")
+        || user_agent.contains("iPhone")
+        || user_agent.contains("iPod")
+        || matches!(os, Some("Android" | "Windows Phone"));
+    if is_mobile {
+        DeviceType::Mobile
+    } else {
+        DeviceType::Desktop
+    }
+}
+
+fn parse_device(user_agent: &str) -> (Option<String>, Option<String>) {
+    for (token, model) in [
+        ("iPad", "iPad"),
+        ("iPhone", "iPhone"),
+        ("iPod", "iPod Touch"),
+        ("Macintosh", "Macintosh"),
+    ] {
+        if user_agent.contains(token) {
+            return (Some("Apple".into()), Some(model.into()));
+        }
+    }
+
+    match parse_android_model(user_agent) {
+        Some(model) => (android_vendor(model), Some(model.to_string())),
+        None => (None, None),
+    }
+}
+
+/// Extracts the model from `Android <version>; [<locale>;] <model>[ Build/...]`.
+fn parse_android_model(user_agent: &str) -> Option<&str> {
+    let rest = find_token(user_agent, "Android ")?;
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let mut rest = rest.strip_prefix(';')?;
+
+    // Older user agents insert the locale before the model, e.g. `; en-us;`.
+    let bytes = rest.as_bytes();
+    if bytes.len() > 6
+        && bytes[0] == b' '
+        && bytes[1..3].iter().all(u8::is_ascii_lowercase)
+        && matches!(bytes[3], b'-' | b'_')
+        && bytes[4..6].iter().all(u8::is_ascii_alphabetic)
+        && bytes[6] == b';'
+    {
+        rest = &rest[7..];
+    }
+
+    let end = [rest.find(';'), rest.find(") ")]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(rest.len());
+    let model = rest[..end].trim();
+    let model = model.split(" Build/").next().unwrap_or(model).trim();
+
+    // Chrome's reduced user agent replaces the model with a single "K".
+    if model.is_empty() || model == "K" || model == "Mobile" {
+        None
+    } else {
+        Some(model)
+    }
+}
+
+fn android_vendor(model: &str) -> Option<String> {
+    ANDROID_VENDORS
+        .iter()
+        .find(|(prefixes, _)| prefixes.iter().any(|p| matches_prefix(model, p)))
+        .map(|(_, vendor)| vendor.to_string())
+        .or_else(|| is_xiaomi_model_number(model).then(|| "Xiaomi".to_string()))
+}
+
+fn matches_prefix(model: &str, prefix: &str) -> bool {
+    let mut model = model.chars();
+    prefix
+        .chars()
+        .all(|expected| match (expected, model.next()) {
+            ('#', Some(c)) => c.is_ascii_digit(),
+            ('@', Some(c)) => c.is_ascii_uppercase(),
+            (expected, Some(c)) => c == expected,
+            (_, None) => false,
+        })
+}
+
+/// Xiaomi model numbers such as `2201117TG` or `23049PCD8G`.
+fn is_xiaomi_model_number(model: &str) -> bool {
+    let bytes = model.as_bytes();
+    (8..=10).contains(&bytes.len())
+        && bytes[..5].iter().all(u8::is_ascii_digit)
+        && bytes[5..]
+            .iter()
+            .all(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+}
+
+/// Parses the client's user agent, then refines it with User-Agent Client Hints
+/// when the browser sent them. Chromium freezes the minor version and device model
+/// in the user agent string, so the hints are the only accurate source there.
+pub fn parse_client(client: &Client) -> UserAgent {
+    let mut user_agent = parse(&client.user_agent);
+
+    let brands = if client.user_agent_full_version_list.trim().is_empty() {
+        parse_brand_list(&client.user_agent_version_list)
+    } else {
+        parse_brand_list(&client.user_agent_full_version_list)
+    };
+    if let Some((browser, version)) = preferred_brand(&brands) {
+        user_agent.browser = Some(browser);
+        user_agent.browser_version = version;
+    }
+
+    if user_agent.device_type != DeviceType::Bot {
+        match parse_mobile_hint(&client.user_agent_mobile) {
+            Some(true) => user_agent.device_type = DeviceType::Mobile,
+            Some(false) if user_agent.os.as_deref() == Some("Android") => {
+                user_agent.device_type = DeviceType::Tablet
+            }
+            Some(false) if user_agent.device_type == DeviceType::Mobile => {
+                user_agent.device_type = DeviceType::Desktop
+            }
+            _ => {}
+        }
+    }
+
+    let model = client.user_agent_model.trim().trim_matches('"').trim();
+    if !model.is_empty() {
+        user_agent.device_vendor = android_vendor(model).or(user_agent.device_vendor);
+        user_agent.device_model = Some(model.to_string());
+    }
+
+    user_agent
+}
+
+/// Parses a `Sec-CH-UA` / `Sec-CH-UA-Full-Version-List` value such as
+/// `"Chromium";v="122.0.6261.112", "Not(A:Brand";v="24.0.0.0"` into
+/// `(brand, version)` pairs, dropping GREASE brands.
+pub fn parse_brand_list(value: &str) -> Vec<(String, String)> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (brand, version) = entry.split_once(";v=")?;
+            let brand = brand.trim().trim_matches('"').trim();
+            let version = version.trim().trim_matches('"').trim();
+            if brand.is_empty() || is_grease_brand(brand) {
+                None
+            } else {
+                Some((brand.to_string(), version.to_string()))
+            }
+        })
+        .collect()
+}
+
+fn is_grease_brand(brand: &str) -> bool {
+    let brand = brand.to_lowercase();
+    brand.contains("not") && brand.contains("brand")
+}
+
+/// Picks the most specific brand: "Chromium" is only used when no vendor brand is listed.
+fn preferred_brand(brands: &[(String, String)]) -> Option<(String, Option<String>)> {
+    let (brand, version) = brands
+        .iter()
+        .find(|(brand, _)| brand != "Chromium")
+        .or_else(|| brands.first())?;
+
+    let browser = match brand.as_str() {
+        "Google Chrome" => "Chrome",
+        "Opera" | "Opera GX" => "Opera",
+        "Yandex" | "YaBrowser" => "Yandex",
+        "HeadlessChrome" => "Headless Chrome",
+        other => other,
+    };
+    let version = Some(version.clone()).filter(|v| !v.is_empty());
+
+    Some((browser.to_string(), version))
+}
+
+fn parse_mobile_hint(value: &str) -> Option<bool> {
+    match value.trim() {
+        "?1" | "1" | "true" => Some(true),
+        "?0" | "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    struct Case {
+        ua: &'static str,
+        browser: Option<&'static str>,
+        browser_version: Option<&'static str>,
+        os: Option<&'static str>,
+        os_version: Option<&'static str>,
+        device_type: DeviceType,
+        device_vendor: Option<&'static str>,
+        device_model: Option<&'static str>,
+    }
+
+    const CORPUS: &[Case] = &[
+        Case {
+            ua: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            browser: Some("Chrome"),
+            browser_version: Some("120.0.0.0"),
+            os: Some("Windows"),
+            os_version: Some("10"),
+            device_type: DeviceType::Desktop,
+            device_vendor: None,
+            device_model: None,
+        },
+        Case {
+            ua: "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Safari/605.1.15",
+            browser: Some("Safari"),
+            browser_version: Some("17.1"),
+            os: Some("Mac OS X"),
+            os_version: Some("10.15.7"),
+            device_type: DeviceType::Desktop,
+            device_vendor: Some("Apple"),
+            device_model: Some("Macintosh"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:121.0) Gecko/20100101 Firefox/121.0",
+            browser: Some("Firefox"),
+            browser_version: Some("121.0"),
+            os: Some("Windows"),
+            os_version: Some("10"),
+            device_type: DeviceType::Desktop,
+            device_vendor: None,
+            device_model: None,
+        },
+        Case {
+            ua: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.2210.91",
+            browser: Some("Microsoft Edge"),
+            browser_version: Some("120.0.2210.91"),
+            os: Some("Windows"),
+            os_version: Some("10"),
+            device_type: DeviceType::Desktop,
+            device_vendor: None,
+            device_model: None,
+        },
+        Case {
+            ua: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/119.0.0.0 Safari/537.36 OPR/105.0.0.0",
+            browser: Some("Opera"),
+            browser_version: Some("105.0.0.0"),
+            os: Some("Windows"),
+            os_version: Some("10"),
+            device_type: DeviceType::Desktop,
+            device_vendor: None,
+            device_model: None,
+        },
+        Case {
+            ua: "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            browser: Some("Chrome"),
+            browser_version: Some("120.0.0.0"),
+            os: Some("Linux"),
+            os_version: None,
+            device_type: DeviceType::Desktop,
+            device_vendor: None,
+            device_model: None,
+        },
+        Case {
+            ua: "Mozilla/5.0 (X11; CrOS x86_64 14541.0.0) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            browser: Some("Chrome"),
+            browser_version: Some("120.0.0.0"),
+            os: Some("Chrome OS"),
+            os_version: Some("14541.0.0"),
+            device_type: DeviceType::Desktop,
+            device_vendor: None,
+            device_model: None,
+        },
+        Case {
+            ua: "Mozilla/5.0 (Windows NT 6.1; WOW64; Trident/7.0; rv:11.0) like Gecko",
+            browser: Some("Internet Explorer"),
+            browser_version: Some("11.0"),
+            os: Some("Windows"),
+            os_version: Some("7"),
+            device_type: DeviceType::Desktop,
+            device_vendor: None,
+            device_model: None,
+        },
+        Case {
+            ua: "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1.2 Mobile/15E148 Safari/604.1",
+            browser: Some("Mobile Safari"),
+            browser_version: Some("17.1.2"),
+            os: Some("iOS"),
+            os_version: Some("17.1.2"),
+            device_type: DeviceType::Mobile,
+            device_vendor: Some("Apple"),
+            device_model: Some("iPhone"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) CriOS/120.0.6099.119 Mobile/15E148 Safari/604.1",
+            browser: Some("Chrome iOS"),
+            browser_version: Some("120.0.6099.119"),
+            os: Some("iOS"),
+            os_version: Some("17.1"),
+            device_type: DeviceType::Mobile,
+            device_vendor: Some("Apple"),
+            device_model: Some("iPhone"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (iPhone; CPU iPhone OS 16_6 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) FxiOS/120.0 Mobile/15E148 Safari/605.1.15",
+            browser: Some("Firefox iOS"),
+            browser_version: Some("120.0"),
+            os: Some("iOS"),
+            os_version: Some("16.6"),
+            device_type: DeviceType::Mobile,
+            device_vendor: Some("Apple"),
+            device_model: Some("iPhone"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (iPad; CPU OS 16_6 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.6 Mobile/15E148 Safari/604.1",
+            browser: Some("Mobile Safari"),
+            browser_version: Some("16.6"),
+            os: Some("iOS"),
+            os_version: Some("16.6"),
+            device_type: DeviceType::Tablet,
+            device_vendor: Some("Apple"),
+            device_model: Some("iPad"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148 [FBAN/FBIOS;FBDV/iPhone14,5;FBMD/iPhone;FBSN/iOS;FBSV/17.0;FBSS/3;FBID/phone;FBLC/en_US;FBOP/5]",
+            browser: Some("Facebook Mobile"),
+            browser_version: None,
+            os: Some("iOS"),
+            os_version: Some("17.0"),
+            device_type: DeviceType::Mobile,
+            device_vendor: Some("Apple"),
+            device_model: Some("iPhone"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148 Instagram 309.0.2.18.96 (iPhone14,2; iOS 17_1; en_US; en; scale=3.00; 1170x2532; 535806534)",
+            browser: Some("Instagram"),
+            browser_version: Some("309.0.2.18.96"),
+            os: Some("iOS"),
+            os_version: Some("17.1"),
+            device_type: DeviceType::Mobile,
+            device_vendor: Some("Apple"),
+            device_model: Some("iPhone"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (Linux; Android 13; SM-S918B) AppleWebKit/537.36 (KHTML, like Gecko) SamsungBrowser/23.0 Chrome/115.0.0.0 Mobile Safari/537.36",
+            browser: Some("Samsung Internet"),
+            browser_version: Some("23.0"),
+            os: Some("Android"),
+            os_version: Some("13"),
+            device_type: DeviceType::Mobile,
+            device_vendor: Some("Samsung"),
+            device_model: Some("SM-S918B"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (Linux; Android 14; Pixel 8 Pro) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.144 Mobile Safari/537.36",
+            browser: Some("Chrome"),
+            browser_version: Some("120.0.6099.144"),
+            os: Some("Android"),
+            os_version: Some("14"),
+            device_type: DeviceType::Mobile,
+            device_vendor: Some("Google"),
+            device_model: Some("Pixel 8 Pro"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+            browser: Some("Chrome"),
+            browser_version: Some("120.0.0.0"),
+            os: Some("Android"),
+            os_version: Some("10"),
+            device_type: DeviceType::Mobile,
+            device_vendor: None,
+            device_model: None,
+        },
+        Case {
+            ua: "Mozilla/5.0 (Linux; Android 13; 2201117TG Build/TKQ1.221114.001; wv) AppleWebKit/537.36 (KHTML, like Gecko) Version/4.0 Chrome/119.0.6045.163 Mobile Safari/537.36",
+            browser: Some("Android WebView"),
+            browser_version: Some("119.0.6045.163"),
+            os: Some("Android"),
+            os_version: Some("13"),
+            device_type: DeviceType::Mobile,
+            device_vendor: Some("Xiaomi"),
+            device_model: Some("2201117TG"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (Linux; Android 12; moto g(60)) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36 OPR/79.2.4195.76529",
+            browser: Some("Opera"),
+            browser_version: Some("79.2.4195.76529"),
+            os: Some("Android"),
+            os_version: Some("12"),
+            device_type: DeviceType::Mobile,
+            device_vendor: Some("Motorola"),
+            device_model: Some("moto g(60)"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (Linux; Android 13; SM-X710) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            browser: Some("Chrome"),
+            browser_version: Some("120.0.0.0"),
+            os: Some("Android"),
+            os_version: Some("13"),
+            device_type: DeviceType::Tablet,
+            device_vendor: Some("Samsung"),
+            device_model: Some("SM-X710"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (Linux; Android 11; KFTRWI) AppleWebKit/537.36 (KHTML, like Gecko) Silk/120.3.1 like Chrome/120.0.6099.230 Safari/537.36",
+            browser: Some("Amazon Silk"),
+            browser_version: Some("120.3.1"),
+            os: Some("Android"),
+            os_version: Some("11"),
+            device_type: DeviceType::Tablet,
+            device_vendor: Some("Amazon"),
+            device_model: Some("KFTRWI"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (Linux; Android 10; CUBOT X30) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+            browser: Some("Chrome"),
+            browser_version: Some("120.0.0.0"),
+            os: Some("Android"),
+            os_version: Some("10"),
+            device_type: DeviceType::Mobile,
+            device_vendor: None,
+            device_model: Some("CUBOT X30"),
+        },
+        Case {
+            ua: "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+            browser: None,
+            browser_version: None,
+            os: None,
+            os_version: None,
+            device_type: DeviceType::Bot,
+            device_vendor: None,
+            device_model: None,
+        },
+        Case {
+            ua: "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/120.0.6099.109 Safari/537.36",
+            browser: Some("Headless Chrome"),
+            browser_version: Some("120.0.6099.109"),
+            os: Some("Linux"),
+            os_version: None,
+            device_type: DeviceType::Bot,
+            device_vendor: None,
+            device_model: None,
+        },
+    ];
+
+    #[test]
+    fn parses_user_agent_corpus() {
+        for case in CORPUS {
+            let parsed = parse(case.ua);
+            let expected = UserAgent {
+                browser: case.browser.map(String::from),
+                browser_version: case.browser_version.map(String::from),
+                os: case.os.map(String::from),
+                os_version: case.os_version.map(String::from),
+                device_type: case.device_type,
+                device_vendor: case.device_vendor.map(String::from),
+                device_model: case.device_model.map(String::from),
+            };
+            assert_eq!(parsed, expected, "user agent: {}", case.ua);
+        }
+    }
+
+    fn client(user_agent: &str) -> Client {
+        Client {
+            ip: String::new(),
+            locale: String::new(),
+            timezone: String::new(),
+            user_agent: user_agent.to_string(),
+            user_agent_architecture: String::new(),
+            user_agent_bitness: String::new(),
+            user_agent_full_version_list: String::new(),
+            user_agent_version_list: String::new(),
+            user_agent_mobile: String::new(),
+            user_agent_model: String::new(),
+            os_name: String::new(),
+            os_version: String::new(),
+            screen_width: 0,
+            screen_height: 0,
+            screen_density: 0.0,
+            continent: String::new(),
+            country_code: String::new(),
+            country_name: String::new(),
+            region: String::new(),
+            city: String::new(),
+        }
+    }
+
+    #[test]
+    fn brand_list_drops_grease() {
+        let brands = parse_brand_list(
+            r#""Chromium";v="122.0.6261.112", "Not(A:Brand";v="24.0.0.0", "Google Chrome";v="122.0.6261.112""#,
+        );
+        assert_eq!(
+            brands,
+            vec![
+                ("Chromium".to_string(), "122.0.6261.112".to_string()),
+                ("Google Chrome".to_string(), "122.0.6261.112".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn client_hints_override_frozen_user_agent() {
+        let mut hinted = client(
+            "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0.0.0 Mobile Safari/537.36",
+        );
+        hinted.user_agent_full_version_list = r#""Not A(Brand";v="99.0.0.0", "Brave";v="122.1.63.165", "Chromium";v="122.0.6261.112""#.to_string();
+        hinted.user_agent_mobile = "?1".to_string();
+        hinted.user_agent_model = r#""SM-S918B""#.to_string();
+
+        let parsed = parse_client(&hinted);
+        assert_eq!(parsed.browser.as_deref(), Some("Brave"));
+        assert_eq!(parsed.browser_version.as_deref(), Some("122.1.63.165"));
+        assert_eq!(parsed.device_type, DeviceType::Mobile);
+        assert_eq!(parsed.device_vendor.as_deref(), Some("Samsung"));
+        assert_eq!(parsed.device_model.as_deref(), Some("SM-S918B"));
+    }
+
+    #[test]
+    fn client_hints_fall_back_to_user_agent() {
+        let plain = client(CORPUS[0].ua);
+        assert_eq!(parse_client(&plain), parse(CORPUS[0].ua));
+
+        let mut chromium_only = client(CORPUS[0].ua);
+        chromium_only.user_agent_version_list = r#""Chromium";v="122""#.to_string();
+        chromium_only.user_agent_mobile = "?0".to_string();
+        let parsed = parse_client(&chromium_only);
+        assert_eq!(parsed.browser.as_deref(), Some("Chromium"));
+        assert_eq!(parsed.browser_version.as_deref(), Some("122"));
+        assert_eq!(parsed.device_type, DeviceType::Desktop);
+    }
+
+    #[test]
+    fn empty_user_agent_is_unknown_desktop() {
+        let parsed = parse("");
+        assert_eq!(parsed.browser, None);
+        assert_eq!(parsed.os, None);
+        assert_eq!(parsed.device_type, DeviceType::Desktop);
+    }
+
+    #[test]
+    fn device_name_follows_mixpanel_js() {
+        let iphone = parse(CORPUS[8].ua);
+        assert_eq!(iphone.device_name(), Some("iPhone".to_string()));
+
+        let pixel = parse(CORPUS[15].ua);
+        assert_eq!(pixel.device_name(), Some("Android".to_string()));
+
+        let desktop = parse(CORPUS[0].ua);
+        assert_eq!(desktop.device_name(), None);
+    }
+
+    /// Per-event parsing cost over the corpus. Run with `make bench`.
+    #[test]
+    #[ignore]
+    fn bench_parse_client() {
+        let clients: Vec<Client> = CORPUS.iter().map(|case| client(case.ua)).collect();
+        let iterations = 200;
+
+        let start = std::time::Instant::now();
+        for _ in 0..iterations {
+            for client in &clients {
+                std::hint::black_box(parse_client(std::hint::black_box(client)));
+            }
+        }
+        let per_event = start.elapsed() / (iterations * clients.len()) as u32;
+
+        println!(
+            "parse_client: {per_event:?} per event ({} user agents x {iterations} iterations)",
+            clients.len()
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use crate::exports::edgee::components::data_collection::{Campaign, PageData};
+use crate::helpers::{insert_if_nonempty, parse_query};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UtmNaming {
+    /// `utm_campaign`, as expected by Mixpanel's attribution reports.
+    Standard,
+    /// `utm_name`, as emitted by earlier versions of this component.
+    Legacy,
+}
+
+impl UtmNaming {
+    pub fn from_settings(settings_map: &HashMap<String, String>) -> anyhow::Result<Self> {
+        match settings_map
+            .get("utm_naming")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("standard") => Ok(UtmNaming::Standard),
+            Some("legacy") => Ok(UtmNaming::Legacy),
+            Some(other) => Err(anyhow::anyhow!(
+                "Invalid 'utm_naming' setting: '{other}' (expected 'standard' or 'legacy')"
+            )),
+        }
+    }
+
+    fn campaign_key(&self) -> &'static str {
+        match self {
+            UtmNaming::Standard => "utm_campaign",
+            UtmNaming::Legacy => "utm_name",
+        }
+    }
+}
+
+/// UTM parameters of a visit. Each one comes from the Edgee `Campaign`
+/// context when set, and from the query string of the page otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UtmParams {
+    pub source: String,
+    pub medium: String,
+    pub campaign: String,
+    pub term: String,
+    pub content: String,
+    pub id: String,
+    pub creative_format: String,
+    pub marketing_tactic: String,
+}
+
+impl UtmParams {
+    pub fn resolve(campaign: &Campaign, page: &PageData) -> Self {
+        let query: Vec<(String, String)> = parse_query(&page.url)
+            .into_iter()
+            .chain(parse_query(&page.search))
+            .collect();
+        let resolve = |context_value: &str, key: &str| {
+            if !context_value.trim().is_empty() {
+                return context_value.to_string();
+            }
+            query
+                .iter()
+                .find(|(k, v)| k == key && !v.trim().is_empty())
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default()
+        };
+
+        Self {
+            source: resolve(&campaign.source, "utm_source"),
+            medium: resolve(&campaign.medium, "utm_medium"),
+            campaign: resolve(&campaign.name, "utm_campaign"),
+            term: resolve(&campaign.term, "utm_term"),
+            content: resolve(&campaign.content, "utm_content"),
+            id: resolve("", "utm_id"),
+            creative_format: resolve(&campaign.creative_format, "utm_creative_format"),
+            marketing_tactic: resolve(&campaign.marketing_tactic, "utm_marketing_tactic"),
+        }
+    }
+
+    pub fn enrich(&self, props: &mut HashMap<String, String>, naming: UtmNaming) {
+        insert_if_nonempty(props, naming.campaign_key(), &self.campaign);
+        insert_if_nonempty(props, "utm_source", &self.source);
+        insert_if_nonempty(props, "utm_medium", &self.medium);
+        insert_if_nonempty(props, "utm_term", &self.term);
+        insert_if_nonempty(props, "utm_content", &self.content);
+        insert_if_nonempty(props, "utm_id", &self.id);
+        insert_if_nonempty(props, "utm_creative_format", &self.creative_format);
+        insert_if_nonempty(props, "utm_marketing_tactic", &self.marketing_tactic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(name: &str, source: &str) -> Campaign {
+        Campaign {
+            name: name.into(),
+            source: source.into(),
+            medium: String::new(),
+            term: String::new(),
+            content: String::new(),
+            creative_format: String::new(),
+            marketing_tactic: String::new(),
+        }
+    }
+
+    fn page(url: &str, search: &str) -> PageData {
+        PageData {
+            name: String::new(),
+            category: String::new(),
+            keywords: vec![],
+            title: String::new(),
+            url: url.into(),
+            path: String::new(),
+            search: search.into(),
+            referrer: String::new(),
+            properties: vec![],
+        }
+    }
+
+    #[test]
+    fn context_wins_over_query_string() {
+        let utm = UtmParams::resolve(
+            &campaign("spring_sale", ""),
+            &page(
+                "https://shop.example.com/?utm_campaign=ignored&utm_source=newsletter&utm_id=42",
+                "",
+            ),
+        );
+        assert_eq!(utm.campaign, "spring_sale");
+        assert_eq!(utm.source, "newsletter");
+        assert_eq!(utm.id, "42");
+    }
+
+    #[test]
+    fn falls_back_to_search_string() {
+        let utm = UtmParams::resolve(
+            &campaign("", ""),
+            &page("", "?utm_medium=email&utm_term=running%20shoes"),
+        );
+        assert_eq!(utm.medium, "email");
+        assert_eq!(utm.term, "running shoes");
+    }
+
+    #[test]
+    fn naming_scheme_selects_campaign_key() {
+        let utm = UtmParams {
+            campaign: "spring_sale".into(),
+            ..Default::default()
+        };
+
+        let mut standard = HashMap::new();
+        utm.enrich(&mut standard, UtmNaming::Standard);
+        assert_eq!(
+            standard.get("utm_campaign"),
+            Some(&"spring_sale".to_string())
+        );
+        assert!(!standard.contains_key("utm_name"));
+
+        let mut legacy = HashMap::new();
+        utm.enrich(&mut legacy, UtmNaming::Legacy);
+        assert_eq!(legacy.get("utm_name"), Some(&"spring_sale".to_string()));
+    }
+}